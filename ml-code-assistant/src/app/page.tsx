@@ -5,18 +5,31 @@ import CodeEditor from '@/components/CodeEditor';
 import AnalysisResults from '@/components/AnalysisResults';
 import { Editor } from '@monaco-editor/react';
 
+interface Span {
+  start_line: number;
+  start_column: number;
+  end_line: number;
+  end_column: number;
+  start_byte: number;
+  end_byte: number;
+}
+
+interface Edit {
+  span: Span;
+  replacement: string;
+}
+
 interface Finding {
+  rule_id?: string;
+  doc_url?: string;
   category: string;
   message: string;
   line: number;
+  span?: Span;
   severity: string;
   fix?: {
     description: string;
-    code: string;
-    range: {
-      start: number;
-      end: number;
-    };
+    edits: Edit[];
   };
 }
 
@@ -98,12 +111,18 @@ export default function Home() {
                   const editor = editorRef.current;
                   const model = editor.getModel();
                   if (model) {
-                    const range = model.getFullModelRange();
-                    editor.executeEdits('auto-fix', [{
-                      range: range,
-                      text: fix.code,
-                    }]);
-                    setCode(fix.code);
+                    editor.executeEdits('auto-fix', fix.edits.map((edit) => ({
+                      range: {
+                        startLineNumber: edit.span.start_line,
+                        startColumn: edit.span.start_column,
+                        endLineNumber: edit.span.end_line,
+                        endColumn: edit.span.end_column,
+                      },
+                      text: edit.replacement,
+                    })));
+                    setCode(model.getValue());
+                    // Spans of the remaining findings refer to the old text.
+                    setFindings([]);
                   }
                 }
               }} 
@@ -2,18 +2,31 @@
 
 import React from 'react';
 
+interface Span {
+  start_line: number;
+  start_column: number;
+  end_line: number;
+  end_column: number;
+  start_byte: number;
+  end_byte: number;
+}
+
+interface Edit {
+  span: Span;
+  replacement: string;
+}
+
 interface Finding {
+  rule_id?: string;
+  doc_url?: string;
   category: string;
   message: string;
   line: number;
+  span?: Span;
   severity: string;
   fix?: {
     description: string;
-    code: string;
-    range: {
-      start: number;
-      end: number;
-    };
+    edits: Edit[];
   };
 }
 
@@ -74,6 +87,16 @@ export default function AnalysisResults({ findings, onApplyFix }: AnalysisResult
                 <div className="flex-1">
                   <div className="flex items-center gap-2 mb-1">
                     <span className="font-medium">{finding.category}</span>
+                    {finding.rule_id && (
+                      <a
+                        href={finding.doc_url}
+                        target="_blank"
+                        rel="noreferrer"
+                        className="text-xs font-mono text-blue-600 hover:underline"
+                      >
+                        {finding.rule_id}
+                      </a>
+                    )}
                     <span className={`text-xs px-2 py-1 rounded ${getSeverityColor(finding.severity)}`}>
                       {finding.severity}
                     </span>
@@ -95,7 +118,7 @@ export default function AnalysisResults({ findings, onApplyFix }: AnalysisResult
                         onClick={() => {
                           if (finding.fix) {
                             const el = document.createElement('textarea');
-                            el.value = finding.fix.code;
+                            el.value = finding.fix.edits.map((edit) => edit.replacement).join('\n');
                             document.body.appendChild(el);
                             el.select();
                             document.execCommand('copy');
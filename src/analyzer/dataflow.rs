@@ -0,0 +1,420 @@
+//! Intra-procedural def-use facts. Walking a function's assignments in order
+//! tags each name with what it holds: a module, an optimizer over a model's
+//! parameters, a tensor computed from a model, and whether it was moved to
+//! the accelerator.
+//!
+//! Names that are never assigned in the function (parameters, globals) or
+//! are assigned something unrecognized fall back to a guess from the name,
+//! so `optimizer` is still an optimizer when it comes in as an argument.
+
+use std::collections::{BTreeSet, HashSet};
+
+use tree_sitter::Node;
+
+use super::scope;
+
+/// `torch.*` functions that create a tensor from scratch.
+const TENSOR_FACTORIES: &[&str] = &[
+    "arange", "as_tensor", "cat", "empty", "empty_like", "from_numpy", "full", "full_like", "linspace", "ones",
+    "ones_like", "rand", "rand_like", "randint", "randn", "randn_like", "stack", "tensor", "zeros", "zeros_like",
+];
+
+/// Methods that return their module or tensor unchanged apart from dtype or mode.
+const PASSTHROUGH_METHODS: &[&str] = &["train", "eval", "half", "float", "double", "bfloat16", "requires_grad_"];
+
+/// Tensor methods and `torch.*` functions whose result has no autograd graph:
+/// indices, comparisons and piecewise-constant functions.
+const GRAPH_CUTTING: &[&str] = &[
+    "argmax", "argmin", "argsort", "round", "sign", "eq", "ne", "lt", "le", "gt", "ge", "greater", "less", "equal",
+];
+
+/// Tensor methods that do not return a tensor.
+const NON_TENSOR_METHODS: &[&str] = &["item", "tolist", "numpy", "size", "dim", "backward"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// An `nn.Module`. Loss modules are tagged so their outputs are not
+    /// counted as depending on them.
+    Module { on_device: bool, loss: bool },
+    /// An optimizer, with the model whose parameters it updates when known.
+    Optimizer { model: Option<String> },
+    Scheduler { optimizer: Option<String> },
+    DataLoader,
+    /// A tensor and the models it was computed from, i.e. whose autograd
+    /// graph it keeps alive. `detach()` leaves none.
+    Tensor { models: BTreeSet<String>, on_device: bool },
+}
+
+impl Value {
+    pub fn on_device(&self) -> bool {
+        matches!(self, Value::Module { on_device: true, .. } | Value::Tensor { on_device: true, .. })
+    }
+
+    /// Whether this is a tensor attached to a model's autograd graph.
+    pub fn has_graph(&self) -> bool {
+        matches!(self, Value::Tensor { models, .. } if !models.is_empty())
+    }
+
+    /// The same tensor cut off from the autograd graph.
+    fn detached(self) -> Option<Value> {
+        match self {
+            Value::Tensor { on_device, .. } => Some(Value::Tensor { models: BTreeSet::new(), on_device }),
+            _ => None,
+        }
+    }
+
+    fn moved(self, on_device: bool) -> Option<Value> {
+        match self {
+            Value::Module { loss, .. } => Some(Value::Module { on_device, loss }),
+            Value::Tensor { models, .. } => Some(Value::Tensor { models, on_device }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    /// Where the binding takes effect: the end of the assignment.
+    at: usize,
+    value: Option<Value>,
+}
+
+/// The bindings of one function, or of a module's top-level code.
+#[derive(Debug)]
+pub struct Dataflow<'a> {
+    code: &'a str,
+    bindings: Vec<Binding>,
+    /// Classes in the file deriving from `nn.Module`.
+    module_classes: HashSet<String>,
+}
+
+impl<'a> Dataflow<'a> {
+    pub fn new(scope: Node, code: &'a str) -> Self {
+        let root = scope::ancestors(scope).last().unwrap_or(scope);
+        let module_classes = scope::all_descendants(root)
+            .filter(|n| n.kind() == "class_definition")
+            .filter(|class| {
+                class
+                    .child_by_field_name("superclasses")
+                    .is_some_and(|bases| text(bases, code).contains("Module"))
+            })
+            .filter_map(|class| class.child_by_field_name("name"))
+            .map(|name| text(name, code).to_string())
+            .collect();
+        let mut dataflow = Dataflow {
+            code,
+            bindings: Vec::new(),
+            module_classes,
+        };
+        for node in scope::descendants(scope) {
+            match node.kind() {
+                "assignment" => dataflow.bind_assignment(node),
+                "for_statement" => dataflow.bind_loop_variables(node),
+                _ => {}
+            }
+        }
+        dataflow
+    }
+
+    /// What `name` holds just before byte `at`.
+    pub fn lookup(&self, name: &str, at: usize) -> Option<Value> {
+        let binding = self.bindings.iter().rev().find(|binding| binding.name == name && binding.at <= at);
+        match binding {
+            Some(Binding { value: Some(value), .. }) => Some(value.clone()),
+            _ => guess(name),
+        }
+    }
+
+    /// What `expr` evaluates to.
+    pub fn value_of(&self, expr: Node) -> Option<Value> {
+        match expr.kind() {
+            "identifier" | "attribute" if is_dotted(expr) => self.lookup(self.text(expr), expr.start_byte()),
+            "parenthesized_expression" => self.value_of(expr.named_child(0)?),
+            "subscript" => match self.value_of(expr.child_by_field_name("value")?)? {
+                tensor @ Value::Tensor { .. } => Some(tensor),
+                _ => None,
+            },
+            "binary_operator" => {
+                let left = self.value_of(expr.child_by_field_name("left")?);
+                let right = self.value_of(expr.child_by_field_name("right")?);
+                merge_tensors([left, right].into_iter().flatten())
+            }
+            "unary_operator" => self.value_of(expr.child_by_field_name("argument")?),
+            "call" => self.value_of_call(expr),
+            _ => None,
+        }
+    }
+
+    /// The model whose forward pass `call` runs, as in `model(x)` or
+    /// `model.forward(x)`. Loss modules are not models.
+    pub fn forward_model(&self, call: Node) -> Option<String> {
+        match self.called_module(call)? {
+            (path, _, false) => Some(path.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The module `call` runs, with where it lives and whether it is a loss.
+    fn called_module(&self, call: Node) -> Option<(&'a str, bool, bool)> {
+        let function = call.child_by_field_name("function")?;
+        let module = match function.child_by_field_name("attribute") {
+            Some(method) if self.text(method) == "forward" => function.child_by_field_name("object")?,
+            _ => function,
+        };
+        let path = self.text(module);
+        let capitalized = path.rsplit('.').next()?.starts_with(|c: char| c.is_ascii_uppercase());
+        if !is_dotted(module) || capitalized {
+            return None;
+        }
+        match self.bindings_value(path, call.start_byte())? {
+            Value::Module { on_device, loss } => Some((path, on_device, loss)),
+            _ => None,
+        }
+    }
+
+    /// The model whose parameters the optimizer `expr` updates.
+    pub fn optimized_model(&self, expr: Node) -> Option<String> {
+        match self.value_of(expr)? {
+            Value::Optimizer { model } => model,
+            _ => None,
+        }
+    }
+
+    pub fn is_optimizer(&self, expr: Node) -> bool {
+        matches!(self.value_of(expr), Some(Value::Optimizer { .. }))
+    }
+
+    fn value_of_call(&self, call: Node) -> Option<Value> {
+        let function = call.child_by_field_name("function")?;
+        let arguments = call.child_by_field_name("arguments");
+        let positional = || {
+            let mut cursor = call.walk();
+            arguments
+                .map(|args| args.named_children(&mut cursor).filter(|n| n.kind() != "keyword_argument").collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+
+        if let Some((path, on_device, loss)) = self.called_module(call) {
+            let mut models = BTreeSet::new();
+            if !loss {
+                models.insert(path.to_string());
+            }
+            let inputs = merge_tensors(positional().into_iter().filter_map(|arg| self.value_of(arg)));
+            let mut on_device = on_device;
+            if let Some(Value::Tensor { models: input_models, on_device: inputs_on_device }) = inputs {
+                models.extend(input_models);
+                on_device |= inputs_on_device;
+            }
+            return Some(Value::Tensor { models, on_device });
+        }
+
+        if function.kind() == "attribute" {
+            let object = function.child_by_field_name("object")?;
+            let method = self.text(function.child_by_field_name("attribute")?);
+            let value = self.value_of(object).filter(|value| matches!(value, Value::Module { .. } | Value::Tensor { .. }));
+            if let Some(value) = value {
+                return match method {
+                    "cuda" => value.moved(true),
+                    "to" if moves_to_device(call, self.code) => value.moved(true),
+                    "to" => Some(value),
+                    "cpu" => value.moved(false),
+                    "detach" | "detach_" => value.detached(),
+                    _ if GRAPH_CUTTING.contains(&method) => value.detached(),
+                    _ if PASSTHROUGH_METHODS.contains(&method) => Some(value),
+                    _ if NON_TENSOR_METHODS.contains(&method) => None,
+                    _ if matches!(value, Value::Tensor { .. }) => Some(value),
+                    _ => None,
+                };
+            }
+        }
+
+        let path = self.text(function);
+        let segments: Vec<&str> = path.split('.').collect();
+        let last = *segments.last()?;
+        let capitalized = last.starts_with(|c: char| c.is_ascii_uppercase());
+        if segments.contains(&"lr_scheduler") && capitalized {
+            let optimizer = positional().first().filter(|arg| is_dotted(**arg)).map(|arg| self.text(*arg).to_string());
+            return Some(Value::Scheduler { optimizer });
+        }
+        if segments.len() > 1 && segments[segments.len() - 2] == "optim" && capitalized {
+            let model = arguments.and_then(|args| self.parameters_owner(args));
+            return Some(Value::Optimizer { model });
+        }
+        if last == "DataLoader" {
+            return Some(Value::DataLoader);
+        }
+        if (segments.contains(&"nn") && capitalized) || (segments.len() == 1 && self.module_classes.contains(last)) {
+            return Some(Value::Module {
+                on_device: false,
+                loss: last.ends_with("Loss"),
+            });
+        }
+        if segments.first() == Some(&"torch") && segments.len() == 2 && TENSOR_FACTORIES.contains(&last) {
+            let on_device = arguments.is_some_and(|args| self.text(args).contains("device"));
+            let inputs = merge_tensors(positional().into_iter().filter_map(|arg| self.value_of(arg)));
+            return match inputs {
+                Some(Value::Tensor { models, on_device: inputs_on_device }) => Some(Value::Tensor {
+                    models,
+                    on_device: on_device || inputs_on_device,
+                }),
+                _ => Some(Value::Tensor {
+                    models: BTreeSet::new(),
+                    on_device,
+                }),
+            };
+        }
+        if matches!(segments.first(), Some(&"torch") | Some(&"F")) {
+            let inputs = merge_tensors(positional().into_iter().filter_map(|arg| self.value_of(arg)));
+            return if GRAPH_CUTTING.contains(&last) { inputs?.detached() } else { inputs };
+        }
+        None
+    }
+
+    /// Like [`Dataflow::lookup`], but without guessing from the name.
+    fn bindings_value(&self, name: &str, at: usize) -> Option<Value> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|binding| binding.name == name && binding.at <= at)
+            .and_then(|binding| binding.value.clone())
+            .or_else(|| guess(name).filter(|value| matches!(value, Value::Module { .. })))
+    }
+
+    /// `model` in `Adam(model.parameters(), ...)`, looking inside wrappers
+    /// such as `filter(...)` and parameter-group lists.
+    fn parameters_owner(&self, arguments: Node) -> Option<String> {
+        scope::all_descendants(arguments).find_map(|node| {
+            let function = node.child_by_field_name("function").filter(|_| node.kind() == "call")?;
+            let method = self.text(function.child_by_field_name("attribute")?);
+            let object = function.child_by_field_name("object")?;
+            (matches!(method, "parameters" | "named_parameters") && is_dotted(object)).then(|| self.text(object).to_string())
+        })
+    }
+
+    fn bind_assignment(&mut self, assignment: Node) {
+        let (Some(left), Some(right)) = (assignment.child_by_field_name("left"), assignment.child_by_field_name("right")) else {
+            return;
+        };
+        let value = self.value_of(right);
+        let at = assignment.end_byte();
+        if is_dotted(left) {
+            self.bind(left, at, value);
+            return;
+        }
+        // `out, hidden = model(x)`: every element of a tensor result is a tensor.
+        let element = value.filter(|value| matches!(value, Value::Tensor { .. }));
+        for target in scope::all_descendants(left).filter(|n| is_dotted(*n) && n.parent().is_some_and(|p| !is_dotted(p))) {
+            self.bind(target, at, element.clone());
+        }
+    }
+
+    /// Batches drawn from a DataLoader are tensors; `enumerate` adds an index.
+    fn bind_loop_variables(&mut self, for_statement: Node) {
+        let (Some(left), Some(right)) = (for_statement.child_by_field_name("left"), for_statement.child_by_field_name("right")) else {
+            return;
+        };
+        let at = left.end_byte();
+        let (iterable, skip_index) = match right.kind() {
+            "call" if right.child_by_field_name("function").is_some_and(|f| self.text(f) == "enumerate") => {
+                match right.child_by_field_name("arguments").and_then(|args| args.named_child(0)) {
+                    Some(inner) => (inner, true),
+                    None => return,
+                }
+            }
+            _ => (right, false),
+        };
+        let batch = matches!(self.value_of(iterable), Some(Value::DataLoader)).then(|| Value::Tensor {
+            models: BTreeSet::new(),
+            on_device: false,
+        });
+
+        let mut targets: Vec<Node> = if is_dotted(left) {
+            vec![left]
+        } else {
+            scope::all_descendants(left).filter(|n| n.kind() == "identifier").collect()
+        };
+        if skip_index && !targets.is_empty() && !is_dotted(left) {
+            let index = targets.remove(0);
+            self.bind(index, at, None);
+        }
+        for target in targets {
+            self.bind(target, at, batch.clone());
+        }
+    }
+
+    fn bind(&mut self, target: Node, at: usize, value: Option<Value>) {
+        let name = self.text(target).to_string();
+        self.bindings.push(Binding { name, at, value });
+    }
+
+    fn text(&self, node: Node) -> &'a str {
+        text(node, self.code)
+    }
+}
+
+/// Whether `call`, a `.to(...)` or `.cuda(...)` call, moves its receiver to
+/// an accelerator. `.to(torch.float16)` only changes the dtype.
+pub fn moves_to_device(call: Node, code: &str) -> bool {
+    let Some(function) = call.child_by_field_name("function") else { return false };
+    if function.child_by_field_name("attribute").is_some_and(|method| text(method, code) == "cuda") {
+        return true;
+    }
+    let Some(arguments) = call.child_by_field_name("arguments") else { return false };
+    let arguments_text = text(arguments, code);
+    if arguments_text.contains("device") || arguments_text.contains("cuda") {
+        return true;
+    }
+    // `.to(dev)`: a bare name is a device; `torch.float16` or `dtype=` is not.
+    arguments.named_child(0).is_some_and(|first| first.kind() == "identifier")
+}
+
+/// A guess from the name alone, for names the function never binds.
+fn guess(name: &str) -> Option<Value> {
+    let last = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+    if last.contains("scheduler") {
+        Some(Value::Scheduler { optimizer: None })
+    } else if last.contains("optim") {
+        Some(Value::Optimizer { model: None })
+    } else if last.contains("loader") {
+        Some(Value::DataLoader)
+    } else if last == "model" || last == "net" || last.ends_with("_model") {
+        Some(Value::Module {
+            on_device: false,
+            loss: false,
+        })
+    } else if last == "criterion" || last.starts_with("loss_f") {
+        Some(Value::Module {
+            on_device: false,
+            loss: true,
+        })
+    } else {
+        None
+    }
+}
+
+/// The tensor combining `values`, if any of them is a tensor.
+fn merge_tensors(values: impl IntoIterator<Item = Value>) -> Option<Value> {
+    let mut merged: Option<(BTreeSet<String>, bool)> = None;
+    for value in values {
+        if let Value::Tensor { models, on_device } = value {
+            let (all, device) = merged.get_or_insert_with(|| (BTreeSet::new(), false));
+            all.extend(models);
+            *device |= on_device;
+        }
+    }
+    merged.map(|(models, on_device)| Value::Tensor { models, on_device })
+}
+
+/// A plain name or attribute chain such as `self.model`.
+fn is_dotted(node: Node) -> bool {
+    match node.kind() {
+        "identifier" => true,
+        "attribute" => node.child_by_field_name("object").is_some_and(is_dotted),
+        _ => false,
+    }
+}
+
+fn text<'a>(node: Node, code: &'a str) -> &'a str {
+    node.utf8_text(code.as_bytes()).unwrap_or("")
+}
@@ -1,299 +1,425 @@
-use anyhow::Result;
-use serde::Serialize;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use tree_sitter::{Parser, Query, QueryCursor};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub(crate) mod dataflow;
+mod project;
+pub mod rules;
+mod scope;
+mod settings;
+mod suppression;
+pub mod training_loop;
+
+use rules::custom::{CustomRule, RuleDefinition};
+use rules::{Captures, Rule, RuleContext};
+use suppression::Suppressions;
+
+pub use dataflow::{Dataflow, Value};
+pub use project::Project;
+pub use settings::{Settings, TorchVersion};
+pub use suppression::{UnusedSuppression, UNUSED_SUPPRESSION};
+pub use training_loop::{LoopKind, Step, StepKind, TrainingLoop};
+
+/// Upper bound on fix rounds, in case two fixes keep undoing each other.
+pub(crate) const MAX_FIX_PASSES: usize = 10;
 
 pub struct CodeAnalyzer {
     parser: Parser,
     query_cache: HashMap<String, Query>,
+    rules: Vec<Box<dyn Rule>>,
+    /// Ids of the rules added by `add_custom_rules`.
+    custom_rules: HashSet<String>,
+    settings: Settings,
+    project: Project,
+    /// Name of the module being analyzed within `project`.
+    module: Option<String>,
 }
 
+/// A suggested change, made of text edits that are applied together.
 #[derive(Debug, Serialize)]
 pub struct Fix {
     pub description: String,
-    pub code: String,
-    pub range: Range,
+    pub edits: Vec<Edit>,
+    pub applicability: Applicability,
 }
 
-#[derive(Debug, Serialize)]
-pub struct Range {
-    pub start: i32,
-    pub end: i32,
+/// Whether a fix can be applied without review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Applicability {
+    /// Keeps the program's behavior apart from the reported issue.
+    Safe,
+    /// May change behavior the author relied on, e.g. adding autocast.
+    Unsafe,
+}
+
+/// Replaces the text covered by `span` with `replacement`. An empty span is an insertion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn insert(code: &str, byte: usize, text: impl Into<String>) -> Self {
+        Edit {
+            span: Span::from_bytes(code, byte, byte),
+            replacement: text.into(),
+        }
+    }
+
+    pub fn replace(code: &str, node: tree_sitter::Node, text: impl Into<String>) -> Self {
+        Edit {
+            span: Span::from_node(code, node),
+            replacement: text.into(),
+        }
+    }
+}
+
+/// A region of source code. Lines and columns are 1-based and columns count
+/// characters; byte offsets are 0-based with an exclusive end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+impl Span {
+    pub fn from_bytes(code: &str, start_byte: usize, end_byte: usize) -> Self {
+        let (start_line, start_column) = Self::position(code, start_byte);
+        let (end_line, end_column) = Self::position(code, end_byte);
+        Span {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+            start_byte,
+            end_byte,
+        }
+    }
+
+    pub fn from_node(code: &str, node: tree_sitter::Node) -> Self {
+        Self::from_bytes(code, node.start_byte(), node.end_byte())
+    }
+
+    fn position(code: &str, byte: usize) -> (usize, usize) {
+        let before = &code[..byte];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        (line, before[line_start..].chars().count() + 1)
+    }
+}
+
+/// How serious a finding is, ordered from least to most severe. Parsing
+/// ignores case, so configuration can say `warning`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Severity::Hint => "hint",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hint" => Ok(Severity::Hint),
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(anyhow::anyhow!("unknown severity '{}', expected error, warning, info or hint", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Severity {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct Finding {
+    /// Stable rule code, e.g. `TG001`.
+    pub rule_id: String,
     pub category: String,
     pub message: String,
     pub line: i32,
-    pub severity: String,
+    pub span: Span,
+    pub severity: Severity,
+    pub doc_url: String,
     pub fix: Option<Fix>,
+    /// For notebooks, the 1-based position of the cell `span` is relative to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell: Option<usize>,
 }
 
 impl CodeAnalyzer {
     pub fn new() -> Result<Self> {
+        Self::with_rules(rules::registry())
+    }
+
+    /// Creates an analyzer running only `rules`.
+    pub fn with_rules(rules: Vec<Box<dyn Rule>>) -> Result<Self> {
         let mut parser = Parser::new();
         parser.set_language(tree_sitter_python::language())?;
         
         Ok(CodeAnalyzer {
             parser,
             query_cache: HashMap::new(),
+            rules,
+            custom_rules: HashSet::new(),
+            settings: Settings::default(),
+            project: Project::default(),
+            module: None,
         })
     }
 
+    /// Adds rules declared in configuration. Their queries are compiled up
+    /// front, so a bad query is reported before anything is analyzed.
+    pub fn add_custom_rules(&mut self, definitions: Vec<RuleDefinition>) -> Result<()> {
+        for definition in definitions {
+            let id = definition.id.clone();
+            if self.rules.iter().any(|rule| rule.id() == id) {
+                anyhow::bail!("custom rule {}: the id is already taken", id);
+            }
+            let query = Self::get_or_create_query(&mut self.query_cache, &definition.query)
+                .with_context(|| format!("custom rule {}: invalid query", id))?;
+            let rule = CustomRule::new(definition, query.capture_names())
+                .with_context(|| format!("custom rule {}", id))?;
+            self.rules.push(Box::new(rule));
+            self.custom_rules.insert(id);
+        }
+        Ok(())
+    }
+
+    /// The rules this analyzer runs, built-in and custom.
+    pub fn rules(&self) -> &[Box<dyn Rule>] {
+        &self.rules
+    }
+
+    /// Settings used by later calls to `analyze` and `fix`.
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    /// Project whose modules rules may follow calls into. Without one, only
+    /// calls within the analyzed code are followed.
+    pub fn set_project(&mut self, project: Project) {
+        self.project = project;
+        self.module = None;
+    }
+
+    /// Marks `path` as the file analyzed next, so its own imports resolve
+    /// within the project.
+    pub fn set_module_path(&mut self, path: &Path) {
+        self.module = self.project.module_for(path).map(str::to_string);
+    }
+
     pub fn analyze(&mut self, code: &str) -> Result<Vec<Finding>> {
-        println!("Analyzing code:\n{}", code);
+        tracing::debug!("Analyzing code:\n{}", code);
         let tree = self.parser.parse(code, None).ok_or_else(|| anyhow::anyhow!("Failed to parse code"))?;
+        let package = self.module.as_deref().and_then(|module| self.project.package_of(module));
+        let module = project::Module::index(code, &tree, package.as_deref());
+        let ctx = RuleContext {
+            code,
+            torch_version: self.settings.torch_version,
+            project: project::ProjectView {
+                project: &self.project,
+                module: &module,
+                name: self.module.as_deref(),
+            },
+            dataflow: Default::default(),
+        };
         let mut findings = Vec::new();
 
-        // Advanced ML patterns query
-        let ml_query = self.get_or_create_query(r#"
-            ; GPU and Memory Operations
-            (call function: (attribute object: (_) @obj attribute: (identifier) @attr) @cuda_call)
-            (with_statement body: (block . (expression_statement (call function: (_) @func))))
-            ; Function definitions
-            (function_definition name: (identifier) @func_name parameters: (parameters) @params body: (block) @body)
-            ; Method calls
-            (call function: (attribute object: (_) @method_obj attribute: (identifier) @method_name) @method_call)
-            ; Variable assignments
-            (assignment left: (_) @assign_left right: (_) @assign_right)
-            ; For loops
-            (for_statement body: (block) @for_body)
-            ; Comments
-            (comment) @comment
-            ; Training operations
-            (call function: (attribute object: (_) @train_obj attribute: (identifier) @train_method) @train_call)
-        "#)?;
-
         let mut query_cursor = QueryCursor::new();
-        let matches = query_cursor.matches(&ml_query, tree.root_node(), code.as_bytes());
-
-        for match_ in matches {
-            for capture in match_.captures {
-                let capture_text = capture.node.utf8_text(code.as_bytes())?;
-                let line_number = capture.node.start_position().row as i32;
-                
-                // Device placement checks
-                if capture_text.contains(".cuda()") {
-                    findings.push(Finding {
-                        category: String::from("GPU Usage"),
-                        message: String::from("Consider using device-agnostic code with .to(device)"),
-                        line: line_number + 1,
-                        severity: String::from("Warning"),
-                        fix: Some(Fix {
-                            description: String::from("Use device-agnostic code"),
-                            code: String::from("device = torch.device('cuda' if torch.cuda.is_available() else 'cpu')\n# Use device-agnostic code\nmodel = model.to(device)"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
+        let enabled = |id: &str| self.settings.is_enabled(id) && (!self.custom_rules.contains(id) || self.settings.declares(id));
+        for rule in self.rules.iter().filter(|rule| enabled(rule.id())) {
+            let query = Self::get_or_create_query(&mut self.query_cache, rule.query())?;
+            let mut candidates = Vec::new();
+            for match_ in query_cursor.matches(query, tree.root_node(), code.as_bytes()) {
+                let captures = Captures::new(query, match_.captures);
+                let Some(target) = captures.target() else { continue };
+                if let Some(mut finding) = rule.check(&ctx, &captures) {
+                    if let Some(&severity) = self.settings.severity.get(rule.id()) {
+                        finding.severity = severity;
+                    }
+                    candidates.push((target.byte_range(), finding));
                 }
+            }
+            findings.extend(Self::most_specific(candidates));
+        }
 
-                // Memory management
-                if capture_text == "backward" && !code.contains("torch.no_grad()") {
-                    findings.push(Finding {
-                        category: String::from("Memory Usage"),
-                        message: String::from("Use torch.no_grad() for inference"),
-                        line: line_number + 1,
-                        severity: String::from("Warning"),
-                        fix: Some(Fix {
-                            description: String::from("Add torch.no_grad() context"),
-                            code: String::from("with torch.no_grad():\n    output = model(data)"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
+        let mut suppressions = Suppressions::parse(code, &tree);
+        let mut findings = suppressions.apply(findings);
+        if self.settings.report_unused_suppressions && self.settings.is_enabled(UNUSED_SUPPRESSION) {
+            findings.extend(suppressions.unused(code).into_iter().map(|mut finding| {
+                if let Some(&severity) = self.settings.severity.get(UNUSED_SUPPRESSION) {
+                    finding.severity = severity;
                 }
+                finding
+            }));
+        }
 
-                // Gradient accumulation
-                if capture_text == "backward" && !code.contains(".zero_grad()") {
-                    findings.push(Finding {
-                        category: "Training".to_string(),
-                        message: "Consider calling optimizer.zero_grad() before backward pass".to_string(),
-                        line: line_number + 1,
-                        severity: "Warning".to_string(),
-                        fix: Some(Fix {
-                            description: "Add optimizer.zero_grad()".to_string(),
-                            code: "optimizer.zero_grad()  # Clear gradients before backward pass\nloss.backward()".to_string(),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+        findings.sort_by_key(|finding| finding.span.start_byte);
+        tracing::debug!("Found {} issues", findings.len());
+        Ok(findings)
+    }
 
-                // DataLoader optimization
-                if capture_text.contains("DataLoader") && !code.contains("num_workers") {
-                    findings.push(Finding {
-                        category: "Performance".into(),
-                        message: "Set num_workers in DataLoader for faster data loading".into(),
-                        line: line_number + 1,
-                        severity: "Info".into(),
-                        fix: None,
-                    });
-                }
+    /// Keeps one finding per node and line for a single rule. When several
+    /// matches start on the same line and one node encloses another, only the
+    /// innermost is reported.
+    fn most_specific(candidates: Vec<(std::ops::Range<usize>, Finding)>) -> Vec<Finding> {
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by_key(|&i| candidates[i].0.len());
 
-                // Mixed precision training
-                if capture_text == "backward" && !code.contains("amp") && !code.contains("autocast") {
-                    findings.push(Finding {
-                        category: "Performance".to_string(),
-                        message: "Consider using mixed precision training with torch.cuda.amp".to_string(),
-                        line: line_number + 1,
-                        severity: "Info".to_string(),
-                        fix: Some(Fix {
-                            description: "Add mixed precision training".to_string(),
-                            code: "scaler = torch.cuda.amp.GradScaler()\nwith torch.cuda.amp.autocast():\n    output = model(data)\n    loss = criterion(output, targets)\nscaler.scale(loss).backward()\nscaler.step(optimizer)\nscaler.update()".to_string(),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+        let mut keep = vec![false; candidates.len()];
+        for i in order {
+            let (range, finding) = &candidates[i];
+            let shadowed = (0..candidates.len()).any(|k| {
+                let (inner, other) = &candidates[k];
+                keep[k] && other.line == finding.line && range.start <= inner.start && inner.end <= range.end
+            });
+            keep[i] = !shadowed;
+        }
 
-                // Batch size power of 2
-                if capture_text.contains("batch_size") {
-                    let batch_size_pattern = regex::Regex::new(r"batch_size\s*=\s*(\d+)").unwrap();
-                    if let Some(caps) = batch_size_pattern.captures(code) {
-                        if let Some(size_str) = caps.get(1) {
-                            if let Ok(size) = size_str.as_str().parse::<i32>() {
-                                if (size & (size - 1)) != 0 {  // Check if not power of 2
-                                    findings.push(Finding {
-                                        category: String::from("Performance"),
-                                        message: String::from("Consider using a power of 2 for batch size for optimal GPU utilization"),
-                                        line: line_number + 1,
-                                        severity: String::from("Info"),
-                                        fix: None,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
+        candidates
+            .into_iter()
+            .zip(keep)
+            .filter_map(|((_, finding), keep)| keep.then_some(finding))
+            .collect()
+    }
 
-                // Model evaluation mode
-                if capture_text == "forward" && !code.contains(".eval()") && code.contains("test_data") {
-                    findings.push(Finding {
-                        category: String::from("Model State"),
-                        message: String::from("Set model.eval() for inference or validation"),
-                        line: line_number + 1,
-                        severity: String::from("Warning"),
-                        fix: Some(Fix {
-                            description: String::from("Add model.eval() before inference"),
-                            code: String::from("model.eval()  # Set model to evaluation mode"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+    /// Applies fixes until none are left, so fixes skipped for overlapping an
+    /// earlier one get another round. Unsafe fixes are only applied when
+    /// `include_unsafe` is set.
+    pub fn fix(&mut self, code: &str, include_unsafe: bool) -> Result<String> {
+        let mut current = code.to_string();
+        let mut applied = AppliedFixes::default();
+        for _ in 0..MAX_FIX_PASSES {
+            let findings = self.analyze(&current)?;
+            let candidates: Vec<&Finding> = findings
+                .iter()
+                .filter(|finding| finding.fix.as_ref().is_some_and(|fix| include_unsafe || fix.applicability == Applicability::Safe))
+                .filter(|finding| applied.is_new(&current, finding))
+                .collect();
+            let edits = select_edits(candidates.iter().filter_map(|finding| finding.fix.as_ref()));
+            applied.record(&current, &candidates, &edits);
+            let fixed = apply_edits(&current, &edits);
+            if fixed == current {
+                break;
+            }
+            current = fixed;
+        }
+        Ok(current)
+    }
 
-                // Check for training operations
-                let capture_text = capture.node.utf8_text(code.as_bytes())?;
-                let line_number = capture.node.start_position().row as i32;
-
-                // Check for GPU operations
-                if capture_text.contains(".cuda()") {
-                    findings.push(Finding {
-                        category: String::from("GPU Usage"),
-                        message: String::from("Consider using device-agnostic code with .to(device)"),
-                        line: line_number + 1,
-                        severity: String::from("Warning"),
-                        fix: Some(Fix {
-                            description: String::from("Use device-agnostic code"),
-                            code: String::from("device = torch.device('cuda' if torch.cuda.is_available() else 'cpu')\n# Use device-agnostic code\nmodel = model.to(device)"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+    fn get_or_create_query<'c>(query_cache: &'c mut HashMap<String, Query>, query: &str) -> Result<&'c Query> {
+        if !query_cache.contains_key(query) {
+            let new_query = Query::new(tree_sitter_python::language(), query)?;
+            query_cache.insert(query.to_string(), new_query);
+        }
+        Ok(query_cache.get(query).unwrap())
+    }
+}
 
-                // Check for backward operations
-                if capture_text == "backward" && !code.contains(".zero_grad()") {
-                    findings.push(Finding {
-                        category: String::from("Training"),
-                        message: String::from("Call optimizer.zero_grad() before backward pass"),
-                        line: line_number + 1,
-                        severity: String::from("Warning"),
-                        fix: Some(Fix {
-                            description: String::from("Add optimizer.zero_grad()"),
-                            code: String::from("optimizer.zero_grad()  # Clear gradients before backward pass"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+/// Fixes applied in earlier passes of a fix loop, keyed by rule, flagged
+/// code and replacements. A fix offered again did not clear its finding, and
+/// applying it again would only repeat its edits, so it is skipped.
+#[derive(Default)]
+pub(crate) struct AppliedFixes(HashSet<(String, String, Vec<String>)>);
 
-                // Check for model evaluation
-                if capture_text == "forward" && !code.contains(".eval()") && code.contains("test_data") {
-                    findings.push(Finding {
-                        category: String::from("Model State"),
-                        message: String::from("Set model.eval() for inference or validation"),
-                        line: line_number + 1,
-                        severity: String::from("Warning"),
-                        fix: Some(Fix {
-                            description: String::from("Add model.eval() before inference"),
-                            code: String::from("model.eval()  # Set model to evaluation mode"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+impl AppliedFixes {
+    fn key(code: &str, finding: &Finding) -> Option<(String, String, Vec<String>)> {
+        let fix = finding.fix.as_ref()?;
+        let flagged = code.get(finding.span.start_byte..finding.span.end_byte).unwrap_or("");
+        let replacements = fix.edits.iter().map(|edit| edit.replacement.clone()).collect();
+        Some((finding.rule_id.clone(), flagged.to_string(), replacements))
+    }
 
-                // Gradient clipping
-                if capture_text == "backward" && !code.contains("clip_grad") {
-                    findings.push(Finding {
-                        category: String::from("Training Stability"),
-                        message: String::from("Consider using gradient clipping for training stability"),
-                        line: line_number + 1,
-                        severity: String::from("Info"),
-                        fix: None,
-                    });
-                }
+    pub(crate) fn is_new(&self, code: &str, finding: &Finding) -> bool {
+        Self::key(code, finding).is_some_and(|key| !self.0.contains(&key))
+    }
 
-                // Learning rate scheduler
-                if capture_text.contains("optimizer") && !code.contains("scheduler") {
-                    findings.push(Finding {
-                        category: String::from("Training"),
-                        message: String::from("Consider using a learning rate scheduler for better convergence"),
-                        line: line_number + 1,
-                        severity: String::from("Info"),
-                        fix: Some(Fix {
-                            description: String::from("Add learning rate scheduler"),
-                            code: String::from("scheduler = torch.optim.lr_scheduler.ReduceLROnPlateau(optimizer, 'min')\nscheduler.step(val_loss)  # Update learning rate based on validation loss"),
-                            range: Range {
-                                start: line_number,
-                                end: line_number + 1,
-                            },
-                        }),
-                    });
-                }
+    /// Remembers the fixes of `findings` whose edits were all `selected`.
+    pub(crate) fn record(&mut self, code: &str, findings: &[&Finding], selected: &[&Edit]) {
+        for finding in findings {
+            let applied = finding.fix.as_ref().is_some_and(|fix| fix.edits.iter().all(|edit| selected.contains(&edit)));
+            if let Some(key) = Self::key(code, finding).filter(|_| applied) {
+                self.0.insert(key);
             }
         }
+    }
+}
 
-        println!("Found {} issues", findings.len());
-        Ok(findings)
+/// Applies `fixes` to `code` and returns the new source.
+pub fn apply_fixes<'a>(code: &str, fixes: impl IntoIterator<Item = &'a Fix>) -> String {
+    apply_edits(code, &select_edits(fixes))
+}
+
+/// Applies `edits`, sorted and non-overlapping, to `code`.
+fn apply_edits(code: &str, edits: &[&Edit]) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut cursor = 0;
+    for edit in edits {
+        output.push_str(&code[cursor..edit.span.start_byte]);
+        output.push_str(&edit.replacement);
+        cursor = edit.span.end_byte;
     }
+    output.push_str(&code[cursor..]);
+    output
+}
 
-    fn get_or_create_query(&mut self, query: &str) -> Result<&Query> {
-        if !self.query_cache.contains_key(query) {
-            let new_query = Query::new(tree_sitter_python::language(), query)?;
-            self.query_cache.insert(query.to_string(), new_query);
+/// The edits of `fixes` that can be applied together, sorted by position.
+///
+/// Identical edits shared by several fixes (such as one import or setup line)
+/// are kept once. A fix with an edit overlapping one already accepted is
+/// skipped as a whole; re-analyzing the result will report it again.
+pub fn select_edits<'a>(fixes: impl IntoIterator<Item = &'a Fix>) -> Vec<&'a Edit> {
+    let mut accepted: Vec<&Edit> = Vec::new();
+    for fix in fixes {
+        let new_edits: Vec<&Edit> = fix.edits.iter().filter(|edit| !accepted.contains(edit)).collect();
+        let overlaps = new_edits.iter().enumerate().any(|(i, edit)| {
+            accepted.iter().chain(&new_edits[..i]).any(|other| edits_overlap(edit, other))
+        });
+        if !overlaps {
+            accepted.extend(new_edits);
         }
-        Ok(self.query_cache.get(query).unwrap())
     }
+
+    accepted.sort_by_key(|edit| (edit.span.start_byte, edit.span.end_byte));
+    accepted
+}
+
+/// Two edits overlap when they touch the same bytes, or both insert at the
+/// same point, where their relative order would be ambiguous.
+fn edits_overlap(a: &Edit, b: &Edit) -> bool {
+    let (a, b) = (a.span, b.span);
+    let both_insert_here = a.start_byte == a.end_byte && b.start_byte == b.end_byte && a.start_byte == b.start_byte;
+    both_insert_here || (a.start_byte < b.end_byte && b.start_byte < a.end_byte)
 }
 
 #[cfg(test)]
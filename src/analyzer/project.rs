@@ -0,0 +1,344 @@
+//! Cross-module facts. A project indexes the functions and imports of every
+//! module, so rules can follow calls out of the code they are looking at,
+//! e.g. into a `build_optimizer()` helper that creates the scheduler.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tree_sitter::{Node, Parser, Tree};
+
+use super::scope;
+
+/// How many imports deep a name is followed, in case of import cycles.
+const MAX_IMPORT_DEPTH: usize = 8;
+
+/// The indexed modules of a Python project.
+#[derive(Debug, Default)]
+pub struct Project {
+    modules: HashMap<String, Module>,
+    /// Names of modules that are packages (`__init__.py`).
+    packages: HashSet<String>,
+    paths: HashMap<PathBuf, String>,
+}
+
+/// The functions and imports of one module.
+#[derive(Debug, Default)]
+pub struct Module {
+    /// Top-level functions, and methods as `Class.method`.
+    functions: HashMap<String, Function>,
+    /// What each imported local name refers to.
+    imports: HashMap<String, Import>,
+}
+
+#[derive(Debug)]
+struct Function {
+    class: Option<String>,
+    /// Dotted callee of every call in the body, e.g. `self.log` or `optim.build`.
+    calls: Vec<String>,
+    identifiers: HashSet<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Import {
+    /// `import a.b as m`
+    Module(String),
+    /// `from a import b as c`; `b` may itself be a module.
+    Name { module: String, name: String },
+}
+
+impl Project {
+    /// Indexes the Python files among `paths`. Files that cannot be read are
+    /// left out; checking them reports the error.
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_python::language())?;
+        let mut project = Project::default();
+        for path in paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "py")) {
+            let Ok(code) = fs::read_to_string(path) else { continue };
+            let Some(tree) = parser.parse(&code, None) else { continue };
+            let (name, is_package) = module_name(path);
+            if is_package {
+                project.packages.insert(name.clone());
+            }
+            let module = Module::index(&code, &tree, project.package_of(&name).as_deref());
+            project.paths.insert(canonical(path), name.clone());
+            project.modules.insert(name, module);
+        }
+        Ok(project)
+    }
+
+    /// Dotted name of the module at `path`, if it is part of the project.
+    pub fn module_for(&self, path: &Path) -> Option<&str> {
+        self.paths.get(&canonical(path)).map(String::as_str)
+    }
+
+    /// The package relative imports in module `name` resolve against.
+    pub fn package_of(&self, name: &str) -> Option<String> {
+        if self.packages.contains(name) {
+            return Some(name.to_string());
+        }
+        name.rsplit_once('.').map(|(package, _)| package.to_string())
+    }
+}
+
+impl Module {
+    /// Indexes a parsed module. `package` is what relative imports resolve against.
+    pub fn index(code: &str, tree: &Tree, package: Option<&str>) -> Self {
+        let mut module = Module::default();
+        let root = tree.root_node();
+        for node in scope::descendants(root).filter(|n| matches!(n.kind(), "import_statement" | "import_from_statement")) {
+            module.index_import(code, node, package);
+        }
+
+        let mut cursor = root.walk();
+        for node in root.children(&mut cursor) {
+            match definition(node) {
+                Some(def) if def.kind() == "function_definition" => module.index_function(code, def, None),
+                Some(def) if def.kind() == "class_definition" => {
+                    let class = def.child_by_field_name("name").map(|name| text(name, code));
+                    let Some(body) = def.child_by_field_name("body") else { continue };
+                    let mut cursor = body.walk();
+                    for method in body.children(&mut cursor).filter_map(definition) {
+                        if method.kind() == "function_definition" {
+                            module.index_function(code, method, class);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        module
+    }
+
+    fn index_function(&mut self, code: &str, def: Node, class: Option<&str>) {
+        let Some(name) = def.child_by_field_name("name").map(|name| text(name, code)) else { return };
+        let key = match class {
+            Some(class) => format!("{}.{}", class, name),
+            None => name.to_string(),
+        };
+        let body = scope::all_descendants(def);
+        let mut calls = Vec::new();
+        let mut identifiers = HashSet::new();
+        for node in body {
+            match node.kind() {
+                "call" => calls.extend(callee_path(node, code)),
+                "identifier" => {
+                    identifiers.insert(text(node, code).to_string());
+                }
+                _ => {}
+            }
+        }
+        self.functions.insert(
+            key,
+            Function {
+                class: class.map(str::to_string),
+                calls,
+                identifiers,
+            },
+        );
+    }
+
+    fn index_import(&mut self, code: &str, node: Node, package: Option<&str>) {
+        let mut cursor = node.walk();
+        let names: Vec<Node> = node.children_by_field_name("name", &mut cursor).collect();
+        if node.kind() == "import_statement" {
+            for name in names {
+                match name.kind() {
+                    // `import a.b` binds `a`.
+                    "dotted_name" => {
+                        let dotted = text(name, code);
+                        let top = dotted.split('.').next().unwrap_or(dotted);
+                        self.imports.insert(top.to_string(), Import::Module(top.to_string()));
+                    }
+                    "aliased_import" => {
+                        let (Some(target), Some(alias)) = (name.child_by_field_name("name"), name.child_by_field_name("alias")) else {
+                            continue;
+                        };
+                        self.imports.insert(text(alias, code).to_string(), Import::Module(text(target, code).to_string()));
+                    }
+                    _ => {}
+                }
+            }
+            return;
+        }
+
+        let Some(from) = node.child_by_field_name("module_name") else { return };
+        let module = match from.kind() {
+            "relative_import" => resolve_relative(text(from, code), package),
+            _ => text(from, code).to_string(),
+        };
+        for name in names {
+            let (imported, local) = match name.kind() {
+                "dotted_name" => (text(name, code), text(name, code)),
+                "aliased_import" => {
+                    let (Some(target), Some(alias)) = (name.child_by_field_name("name"), name.child_by_field_name("alias")) else {
+                        continue;
+                    };
+                    (text(target, code), text(alias, code))
+                }
+                _ => continue,
+            };
+            self.imports.insert(
+                local.to_string(),
+                Import::Name {
+                    module: module.clone(),
+                    name: imported.to_string(),
+                },
+            );
+        }
+    }
+}
+
+/// The project as seen from the module being analyzed, whose index is built
+/// from the current source rather than the copy on disk.
+pub struct ProjectView<'a> {
+    pub project: &'a Project,
+    pub module: &'a Module,
+    pub name: Option<&'a str>,
+}
+
+type FunctionRef = (Option<String>, String);
+
+impl ProjectView<'_> {
+    /// Whether a function reachable from `callees` — directly or through
+    /// further calls, in this module or an imported one — mentions an
+    /// identifier satisfying `pred`. `class` resolves `self.method` calls.
+    pub fn reaches(&self, callees: impl IntoIterator<Item = String>, class: Option<&str>, pred: impl Fn(&str) -> bool) -> bool {
+        let current = self.name.map(str::to_string);
+        let mut queue: VecDeque<FunctionRef> =
+            callees.into_iter().filter_map(|callee| self.resolve(current.as_deref(), class, &callee)).collect();
+        let mut seen: HashSet<FunctionRef> = HashSet::new();
+        while let Some(function_ref) = queue.pop_front() {
+            if !seen.insert(function_ref.clone()) {
+                continue;
+            }
+            let (module_name, key) = &function_ref;
+            let Some(function) = self.module(module_name.as_deref()).and_then(|module| module.functions.get(key)) else {
+                continue;
+            };
+            if function.identifiers.iter().any(|id| pred(id)) {
+                return true;
+            }
+            queue.extend(
+                function
+                    .calls
+                    .iter()
+                    .filter_map(|callee| self.resolve(module_name.as_deref(), function.class.as_deref(), callee)),
+            );
+        }
+        false
+    }
+
+    fn module(&self, name: Option<&str>) -> Option<&Module> {
+        if name == self.name {
+            Some(self.module)
+        } else {
+            self.project.modules.get(name?)
+        }
+    }
+
+    fn resolve(&self, module: Option<&str>, class: Option<&str>, callee: &str) -> Option<FunctionRef> {
+        let parts: Vec<&str> = callee.split('.').collect();
+        if let (["self", method], Some(class)) = (parts.as_slice(), class) {
+            let key = format!("{}.{}", class, method);
+            if self.module(module)?.functions.contains_key(&key) {
+                return Some((module.map(str::to_string), key));
+            }
+        }
+        self.resolve_in(module, &parts, 0)
+    }
+
+    /// Resolves `parts` as seen from inside `module`.
+    fn resolve_in(&self, module_name: Option<&str>, parts: &[&str], depth: usize) -> Option<FunctionRef> {
+        if depth > MAX_IMPORT_DEPTH || parts.is_empty() {
+            return None;
+        }
+        let module = self.module(module_name)?;
+        let joined = parts.join(".");
+        for key in [joined.clone(), format!("{}.__init__", joined)] {
+            if module.functions.contains_key(&key) {
+                return Some((module_name.map(str::to_string), key));
+            }
+        }
+        match module.imports.get(parts[0])? {
+            Import::Module(target) => self.resolve_path(target, &parts[1..], depth + 1),
+            Import::Name { module, name } => {
+                let submodule = format!("{}.{}", module, name);
+                if self.project.modules.contains_key(&submodule) {
+                    self.resolve_path(&submodule, &parts[1..], depth + 1)
+                } else {
+                    let renamed: Vec<&str> = std::iter::once(name.as_str()).chain(parts[1..].iter().copied()).collect();
+                    self.resolve_in(Some(module), &renamed, depth + 1)
+                }
+            }
+        }
+    }
+
+    /// Resolves `rest` as an attribute path on module `target`.
+    fn resolve_path(&self, target: &str, rest: &[&str], depth: usize) -> Option<FunctionRef> {
+        let first = rest.first()?;
+        let submodule = format!("{}.{}", target, first);
+        if self.project.modules.contains_key(&submodule) {
+            self.resolve_path(&submodule, &rest[1..], depth + 1)
+        } else {
+            self.resolve_in(Some(target), rest, depth + 1)
+        }
+    }
+}
+
+/// The dotted callee of `call`, for plain names and attribute chains only.
+pub fn callee_path(call: Node, code: &str) -> Option<String> {
+    let function = call.child_by_field_name("function")?;
+    let mut node = function;
+    while node.kind() == "attribute" {
+        node = node.child_by_field_name("object")?;
+    }
+    (node.kind() == "identifier").then(|| text(function, code).split_whitespace().collect())
+}
+
+/// The dotted module name of `path` and whether it is a package, found by
+/// walking up through directories that contain an `__init__.py`.
+fn module_name(path: &Path) -> (String, bool) {
+    let path = canonical(path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let is_package = stem == "__init__";
+    let mut parts = if is_package { Vec::new() } else { vec![stem.to_string()] };
+    let mut dir = path.parent();
+    while let Some(current) = dir.filter(|dir| dir.join("__init__.py").is_file()) {
+        parts.push(current.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string());
+        dir = current.parent();
+    }
+    parts.reverse();
+    (parts.join("."), is_package)
+}
+
+/// `..models` seen from package `pkg.train` is `pkg.models`.
+fn resolve_relative(relative: &str, package: Option<&str>) -> String {
+    let dots = relative.len() - relative.trim_start_matches('.').len();
+    let mut parts: Vec<&str> = package.map_or(Vec::new(), |package| package.split('.').collect());
+    parts.truncate(parts.len().saturating_sub(dots - 1));
+    let rest = &relative[dots..];
+    if !rest.is_empty() {
+        parts.push(rest);
+    }
+    parts.join(".")
+}
+
+/// A definition, looking through decorators.
+fn definition(node: Node) -> Option<Node> {
+    match node.kind() {
+        "function_definition" | "class_definition" => Some(node),
+        "decorated_definition" => node.child_by_field_name("definition"),
+        _ => None,
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn text<'a>(node: Node, code: &'a str) -> &'a str {
+    node.utf8_text(code.as_bytes()).unwrap_or("")
+}
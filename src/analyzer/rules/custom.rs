@@ -0,0 +1,201 @@
+//! Rules declared in configuration rather than code: a tree-sitter query,
+//! conditions on its captures, a message and an optional replacement.
+//!
+//! ```toml
+//! [[rules]]
+//! id = "ACME001"
+//! message = "Save `{target}.state_dict()` rather than the whole model"
+//! query = """
+//! (call
+//!     function: (attribute object: (identifier) @module attribute: (identifier) @method)
+//!     arguments: (argument_list . (identifier) @target))
+//! """
+//! replacement = "{target}.state_dict()"
+//!
+//! [[rules.where]]
+//! capture = "module"
+//! equals = "torch"
+//!
+//! [[rules.where]]
+//! capture = "method"
+//! equals = "save"
+//! ```
+//!
+//! `{name}` in the message and replacement stands for the text of capture
+//! `@name`. The replacement is applied to `@target` as an unsafe fix.
+
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+
+use super::{unsafe_fix, Captures, Rule, RuleContext};
+use crate::analyzer::{Edit, Finding, Severity};
+
+pub const CATEGORY: &str = "Custom";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RuleDefinition {
+    pub id: String,
+    pub query: String,
+    pub message: String,
+    /// One-line description; the message when omitted.
+    pub summary: Option<String>,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// Template replacing the `@target` capture.
+    pub replacement: Option<String>,
+    pub url: Option<String>,
+    /// Conditions every match must meet.
+    #[serde(default, rename = "where")]
+    pub conditions: Vec<Condition>,
+}
+
+/// A condition on the text of one capture. A capture that did not take part
+/// in the match meets no condition.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Condition {
+    pub capture: String,
+    pub equals: Option<String>,
+    pub not_equals: Option<String>,
+    /// Regex that must match somewhere in the text; anchor it to match all of it.
+    pub matches: Option<String>,
+    pub not_matches: Option<String>,
+}
+
+fn default_severity() -> Severity {
+    Severity::Warning
+}
+
+fn default_category() -> String {
+    CATEGORY.to_string()
+}
+
+pub struct CustomRule {
+    definition: RuleDefinition,
+    conditions: Vec<CompiledCondition>,
+}
+
+struct CompiledCondition {
+    capture: String,
+    equals: Option<String>,
+    not_equals: Option<String>,
+    matches: Option<Regex>,
+    not_matches: Option<Regex>,
+}
+
+impl CustomRule {
+    /// Checks `definition` against the names its compiled query captures.
+    pub fn new(definition: RuleDefinition, capture_names: &[String]) -> Result<Self> {
+        if !id_pattern().is_match(&definition.id) {
+            bail!("invalid rule id '{}': use letters, digits and underscores, starting with a letter", definition.id);
+        }
+        let known = |name: &str| capture_names.iter().any(|capture| capture == name);
+        if !known("target") {
+            bail!("the query has no @target capture");
+        }
+        let templates = [Some(definition.message.as_str()), definition.replacement.as_deref()];
+        for name in templates.into_iter().flatten().flat_map(placeholders) {
+            if !known(name) {
+                bail!("unknown capture {{{}}} in template", name);
+            }
+        }
+
+        let regex = |pattern: &Option<String>| {
+            pattern
+                .as_deref()
+                .map(|pattern| Regex::new(pattern).with_context(|| format!("invalid regex '{}'", pattern)))
+                .transpose()
+        };
+        let mut conditions = Vec::new();
+        for condition in &definition.conditions {
+            if !known(&condition.capture) {
+                bail!("condition on unknown capture @{}", condition.capture);
+            }
+            conditions.push(CompiledCondition {
+                capture: condition.capture.clone(),
+                equals: condition.equals.clone(),
+                not_equals: condition.not_equals.clone(),
+                matches: regex(&condition.matches)?,
+                not_matches: regex(&condition.not_matches)?,
+            });
+        }
+        Ok(CustomRule { definition, conditions })
+    }
+}
+
+impl Rule for CustomRule {
+    fn id(&self) -> &str {
+        &self.definition.id
+    }
+
+    fn summary(&self) -> &str {
+        self.definition.summary.as_deref().unwrap_or(&self.definition.message)
+    }
+
+    fn category(&self) -> &str {
+        &self.definition.category
+    }
+
+    fn severity(&self) -> Severity {
+        self.definition.severity
+    }
+
+    fn query(&self) -> &str {
+        &self.definition.query
+    }
+
+    fn doc_url(&self) -> String {
+        self.definition.url.clone().unwrap_or_default()
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        for condition in &self.conditions {
+            let text = ctx.text(captures.get(&condition.capture)?);
+            let holds = condition.equals.as_ref().is_none_or(|expected| text == expected)
+                && condition.not_equals.as_ref().is_none_or(|unexpected| text != unexpected)
+                && condition.matches.as_ref().is_none_or(|regex| regex.is_match(text))
+                && condition.not_matches.as_ref().is_none_or(|regex| !regex.is_match(text));
+            if !holds {
+                return None;
+            }
+        }
+
+        let fill = |template: &str| {
+            placeholder_pattern()
+                .replace_all(template, |caps: &regex::Captures| {
+                    captures.get(&caps[1]).map_or("", |node| ctx.text(node)).to_string()
+                })
+                .into_owned()
+        };
+        let fix = self.definition.replacement.as_deref().map(|template| {
+            let replacement = fill(template);
+            unsafe_fix(&format!("Replace with `{}`", replacement), vec![Edit::replace(ctx.code, target, replacement)])
+        });
+        Some(self.report(ctx, target, &fill(&self.definition.message), fix))
+    }
+}
+
+fn placeholders(template: &str) -> Vec<&str> {
+    placeholder_pattern()
+        .captures_iter(template)
+        .filter_map(|caps| caps.get(1).map(|name| name.as_str()))
+        .collect()
+}
+
+fn placeholder_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{(\w+)\}").unwrap())
+}
+
+/// Ids must survive suppression comments, which list codes as words.
+fn id_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^[A-Za-z]\w*$").unwrap())
+}
@@ -0,0 +1,357 @@
+//! Per-call checks of `DataLoader(...)` keyword arguments. Findings about an
+//! argument point at it; findings about a missing one point at the call.
+
+use tree_sitter::Node;
+
+use super::{append_argument, fix, keyword_argument, unsafe_fix, Captures, Rule, RuleContext};
+use crate::analyzer::dataflow::moves_to_device;
+use crate::analyzer::{scope, Edit, Finding, Severity, Value};
+
+/// Matches every `DataLoader(...)` and `torch.utils.data.DataLoader(...)` call.
+pub(crate) const DATALOADER_CALL: &str = r#"
+    (call
+        function: [
+            (identifier) @name
+            (attribute attribute: (identifier) @name)
+        ]
+        arguments: (argument_list) @arguments
+        (#eq? @name "DataLoader")) @target
+"#;
+
+const CATEGORY: &str = "Data Loading";
+
+/// Words in a loader or dataset name that mark held-out data.
+const HELD_OUT_WORDS: &[&str] = &["val", "valid", "validation", "test", "eval", "evaluation", "dev", "holdout"];
+
+/// Classes whose training-mode forward pass fails on a batch of one.
+const BATCH_NORMS: &[&str] = &["BatchNorm1d", "BatchNorm2d", "BatchNorm3d", "SyncBatchNorm"];
+
+/// TG027: pageable host memory is copied through a staging buffer and
+/// cannot overlap with compute.
+pub struct MissingPinMemory;
+
+impl Rule for MissingPinMemory {
+    fn id(&self) -> &'static str {
+        "TG027"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`DataLoader` without `pin_memory=True` feeding the GPU"
+    }
+
+    fn category(&self) -> &'static str {
+        CATEGORY
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let arguments = captures.get("arguments")?;
+        if keyword_argument(ctx, arguments, "pin_memory").is_some() || !batches_moved_to_device(ctx, target) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "batches from this DataLoader are copied to the GPU; pass pin_memory=True and move them with .to(device, non_blocking=True)",
+            append_argument(ctx, arguments, "pin_memory=True").map(|edit| fix("Add pin_memory=True", vec![edit])),
+        ))
+    }
+}
+
+/// TG028: without `persistent_workers`, worker processes are started again,
+/// dataset included, at the start of every epoch.
+pub struct WorkersNotPersistent;
+
+impl Rule for WorkersNotPersistent {
+    fn id(&self) -> &'static str {
+        "TG028"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`num_workers` without `persistent_workers`"
+    }
+
+    fn category(&self) -> &'static str {
+        CATEGORY
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let arguments = captures.get("arguments")?;
+        let workers = keyword_argument(ctx, arguments, "num_workers").filter(|value| !is_zero(ctx, *value))?;
+        if keyword_argument(ctx, arguments, "persistent_workers").is_some() {
+            return None;
+        }
+        let argument = workers.parent()?;
+        Some(self.report(
+            ctx,
+            argument,
+            "worker processes are restarted every epoch; pass persistent_workers=True to keep them",
+            Some(unsafe_fix(
+                "Add persistent_workers=True",
+                vec![Edit::insert(ctx.code, argument.end_byte(), ", persistent_workers=True")],
+            )),
+        ))
+    }
+}
+
+/// TG029: shuffling held-out data only costs random reads and makes
+/// per-sample outputs harder to compare between runs.
+pub struct ShuffledEvaluation;
+
+impl Rule for ShuffledEvaluation {
+    fn id(&self) -> &'static str {
+        "TG029"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Validation or test `DataLoader` with `shuffle=True`"
+    }
+
+    fn category(&self) -> &'static str {
+        CATEGORY
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let shuffle = keyword_argument(ctx, captures.get("arguments")?, "shuffle").filter(|value| ctx.text(*value) == "True")?;
+        if !is_held_out(ctx, target) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            shuffle.parent()?,
+            "shuffling validation or test data changes nothing but the order of the outputs; use shuffle=False",
+            Some(unsafe_fix("Set shuffle=False", vec![Edit::replace(ctx.code, shuffle, "False")])),
+        ))
+    }
+}
+
+/// TG030: a last batch of one sample makes batch norm raise in training mode.
+pub struct BatchNormWithoutDropLast;
+
+impl Rule for BatchNormWithoutDropLast {
+    fn id(&self) -> &'static str {
+        "TG030"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Training `DataLoader` without `drop_last` for a model with batch norm"
+    }
+
+    fn category(&self) -> &'static str {
+        CATEGORY
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let arguments = captures.get("arguments")?;
+        if keyword_argument(ctx, arguments, "drop_last").is_some() || is_held_out(ctx, target) {
+            return None;
+        }
+        let shuffled = keyword_argument(ctx, arguments, "shuffle").is_some_and(|value| ctx.text(value) == "True");
+        let named_train = loader_name(ctx, target).is_some_and(|name| words(name).any(|word| word == "train"));
+        if !(shuffled || named_train) || !uses_batch_norm(ctx, target) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "the last batch can hold a single sample, which batch norm rejects in training mode; pass drop_last=True",
+            append_argument(ctx, arguments, "drop_last=True").map(|edit| unsafe_fix("Add drop_last=True", vec![edit])),
+        ))
+    }
+}
+
+/// TG031: `prefetch_factor` only applies to worker processes.
+pub struct PrefetchWithoutWorkers;
+
+impl Rule for PrefetchWithoutWorkers {
+    fn id(&self) -> &'static str {
+        "TG031"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`prefetch_factor` without worker processes"
+    }
+
+    fn category(&self) -> &'static str {
+        CATEGORY
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let arguments = captures.get("arguments")?;
+        // `prefetch_factor=None` is the default, which PyTorch accepts without workers.
+        let prefetch = keyword_argument(ctx, arguments, "prefetch_factor").filter(|value| value.kind() != "none")?;
+        if keyword_argument(ctx, arguments, "num_workers").is_some_and(|value| !is_zero(ctx, value)) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            prefetch.parent()?,
+            "prefetch_factor needs num_workers > 0; PyTorch 2.0 and later raise a ValueError, older releases ignore it",
+            None,
+        ))
+    }
+}
+
+/// TG032: worker processes receive the loader's callables by pickling them,
+/// which fails for lambdas under the `spawn` start method (Windows, macOS).
+pub struct LambdaInWorkers;
+
+impl Rule for LambdaInWorkers {
+    fn id(&self) -> &'static str {
+        "TG032"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Lambda passed as `collate_fn` or `worker_init_fn` with worker processes"
+    }
+
+    fn category(&self) -> &'static str {
+        CATEGORY
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let arguments = captures.get("arguments")?;
+        keyword_argument(ctx, arguments, "num_workers").filter(|value| !is_zero(ctx, *value))?;
+        let lambda = ["collate_fn", "worker_init_fn"]
+            .into_iter()
+            .filter_map(|name| keyword_argument(ctx, arguments, name))
+            .find(|value| value.kind() == "lambda")?;
+        let argument = lambda.parent()?;
+        let name = ctx.text(argument.child_by_field_name("name")?);
+        Some(self.report(
+            ctx,
+            argument,
+            &format!(
+                "a lambda as {} cannot be pickled for worker processes started with spawn; use a module-level function or functools.partial",
+                name
+            ),
+            None,
+        ))
+    }
+}
+
+/// Whether `value` is the literal `0`.
+fn is_zero(ctx: &RuleContext, value: Node) -> bool {
+    value.kind() == "integer" && ctx.text(value) == "0"
+}
+
+/// The name the loader created by `call` is assigned to.
+fn loader_name<'a>(ctx: &RuleContext<'a>, call: Node) -> Option<&'a str> {
+    let assignment = call.parent().filter(|parent| parent.kind() == "assignment")?;
+    assignment.child_by_field_name("left").filter(|left| matches!(left.kind(), "identifier" | "attribute")).map(|left| ctx.text(left))
+}
+
+/// The lowercase words of a name such as `self.val_loader`.
+fn words(name: &str) -> impl Iterator<Item = String> + '_ {
+    name.split(['_', '.']).map(str::to_lowercase)
+}
+
+/// Whether the loader created by `call` serves validation or test data,
+/// judging by its name or its dataset's.
+fn is_held_out(ctx: &RuleContext, call: Node) -> bool {
+    let dataset = call.child_by_field_name("arguments").and_then(|arguments| {
+        keyword_argument(ctx, arguments, "dataset").or_else(|| arguments.named_child(0).filter(|first| first.kind() != "keyword_argument"))
+    });
+    let dataset = dataset.filter(|dataset| matches!(dataset.kind(), "identifier" | "attribute")).map(|dataset| ctx.text(dataset));
+    loader_name(ctx, call)
+        .into_iter()
+        .chain(dataset)
+        .any(|name| words(name).any(|word| HELD_OUT_WORDS.contains(&word.as_str())))
+}
+
+/// The name of the function that returns the loader `call` creates, as in
+/// `def make_loader(): return DataLoader(...)`.
+fn returned_from<'a>(ctx: &RuleContext<'a>, call: Node) -> Option<&'a str> {
+    call.parent().filter(|parent| parent.kind() == "return_statement")?;
+    let function = scope::enclosing_function(call).filter(|function| function.kind() == "function_definition")?;
+    Some(ctx.text(function.child_by_field_name("name")?))
+}
+
+/// Whether the file builds a model with batch norm layers.
+fn uses_batch_norm(ctx: &RuleContext, node: Node) -> bool {
+    let root = scope::ancestors(node).last().unwrap_or(node);
+    scope::all_descendants(root).any(|n| n.kind() == "identifier" && BATCH_NORMS.contains(&ctx.text(n)))
+}
+
+/// Whether a loop in the file iterates over the loader created by `call` and
+/// moves what it draws to an accelerator. Without a name for the loader, the
+/// loop must iterate over `call` itself, or over a call to the function that
+/// returns it.
+fn batches_moved_to_device(ctx: &RuleContext, call: Node) -> bool {
+    let name = loader_name(ctx, call).map(|name| name.rsplit('.').next().unwrap_or(name));
+    let factory = returned_from(ctx, call);
+    let root = scope::ancestors(call).last().unwrap_or(call);
+    scope::all_descendants(root).filter(|n| n.kind() == "for_statement").any(|for_statement| {
+        let Some(iterable) = for_statement.child_by_field_name("right") else { return false };
+        let mut nodes = std::iter::once(iterable).chain(scope::all_descendants(iterable));
+        let over_loader = match (name, factory) {
+            (Some(name), _) => nodes.any(|n| n.kind() == "identifier" && ctx.text(n) == name),
+            (None, Some(factory)) => nodes.any(|n| {
+                n.kind() == "call" && n.child_by_field_name("function").is_some_and(|f| ctx.text(f).rsplit('.').next() == Some(factory))
+            }),
+            (None, None) => nodes.any(|n| n == call),
+        };
+        over_loader
+            && for_statement.child_by_field_name("body").is_some_and(|body| {
+                scope::all_descendants(body).any(|n| {
+                    let Some(function) = n.child_by_field_name("function").filter(|_| n.kind() == "call") else { return false };
+                    let (Some(object), Some(method)) = (function.child_by_field_name("object"), function.child_by_field_name("attribute")) else {
+                        return false;
+                    };
+                    matches!(ctx.text(method), "to" | "cuda")
+                        && moves_to_device(n, ctx.code)
+                        && !matches!(ctx.dataflow(object).value_of(object), Some(Value::Module { .. }))
+                })
+            })
+    })
+}
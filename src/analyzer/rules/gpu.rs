@@ -0,0 +1,195 @@
+use tree_sitter::Node;
+
+use super::{fix, insert_line_after_imports, unsafe_fix, Captures, Rule, RuleContext};
+use crate::analyzer::dataflow::moves_to_device;
+use crate::analyzer::{scope, Edit, Finding, Severity};
+
+const DEVICE_LINE: &str = "device = torch.device('cuda' if torch.cuda.is_available() else 'cpu')";
+
+/// TG001: `.cuda()` hard-codes the device; `.to(device)` runs anywhere.
+pub struct RawCuda;
+
+impl Rule for RawCuda {
+    fn id(&self) -> &'static str {
+        "TG001"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Raw `.cuda()` call"
+    }
+
+    fn category(&self) -> &'static str {
+        "GPU Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            arguments: (argument_list) @arguments
+            (#eq? @method "cuda")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let object = captures.get("object")?;
+
+        // `.cuda(0)` and `.cuda(device=1)` name a device, which `device`
+        // replaces; other keyword arguments such as `non_blocking=True`
+        // carry over to `.to()`.
+        let mut cursor = target.walk();
+        let (keywords, positional): (Vec<_>, Vec<_>) = captures
+            .get("arguments")?
+            .named_children(&mut cursor)
+            .filter(|n| n.kind() != "comment")
+            .partition(|n| n.kind() == "keyword_argument");
+        let (explicit_device, keywords): (Vec<_>, Vec<_>) = keywords
+            .into_iter()
+            .partition(|n| n.child_by_field_name("name").is_some_and(|name| ctx.text(name) == "device"));
+        let mut arguments = vec!["device".to_string()];
+        arguments.extend(keywords.iter().map(|n| ctx.text(*n).to_string()));
+
+        let mut edits = vec![Edit::replace(
+            ctx.code,
+            target,
+            format!("{}.to({})", ctx.text(object), arguments.join(", ")),
+        )];
+        // The inserted line needs `torch`; without a module-level import
+        // above it, the fixed file would raise a NameError.
+        let mut needs_torch = false;
+        if !defines_device(ctx, target) {
+            let edit = insert_line_after_imports(ctx, target, DEVICE_LINE);
+            needs_torch = !imports_torch_before(ctx, target, edit.span.start_byte);
+            edits.push(edit);
+        }
+
+        Some(self.report(
+            ctx,
+            target,
+            "Consider using device-agnostic code with .to(device)",
+            Some(if positional.is_empty() && explicit_device.is_empty() && !needs_torch {
+                fix("Use device-agnostic code", edits)
+            } else {
+                unsafe_fix("Use device-agnostic code", edits)
+            }),
+        ))
+    }
+}
+
+/// Whether a `device` name is bound where `node` runs: by a parameter or an
+/// assignment of the enclosing function, or by an assignment or import at
+/// module level. Mentions such as `torch.device(...)` do not count.
+fn defines_device(ctx: &RuleContext, node: Node) -> bool {
+    let module = scope::ancestors(node).last().unwrap_or(node);
+    let binds = |scope: Node| scope::descendants(scope).any(|n| binds_device(ctx, n));
+    let function = scope::enclosing_function(node).filter(|function| function.kind() == "function_definition");
+    let parameter = function.and_then(|function| function.child_by_field_name("parameters")).is_some_and(|parameters| {
+        let mut cursor = parameters.walk();
+        let found = parameters.named_children(&mut cursor).any(|parameter| {
+            let name = match parameter.kind() {
+                "identifier" => Some(parameter),
+                "default_parameter" | "typed_default_parameter" => parameter.child_by_field_name("name"),
+                "typed_parameter" => parameter.named_child(0),
+                _ => None,
+            };
+            name.is_some_and(|name| ctx.text(name) == "device")
+        });
+        found
+    });
+    parameter || function.is_some_and(binds) || binds(module)
+}
+
+/// Whether a module-level `import torch` (or `import torch.x`) ends before byte `at`.
+fn imports_torch_before(ctx: &RuleContext, node: Node, at: usize) -> bool {
+    let module = scope::ancestors(node).last().unwrap_or(node);
+    let mut cursor = module.walk();
+    let found = module
+        .children(&mut cursor)
+        .filter(|statement| statement.kind() == "import_statement" && statement.end_byte() <= at)
+        .any(|statement| {
+            let mut cursor = statement.walk();
+            let found = statement.children_by_field_name("name", &mut cursor).any(|name| {
+                name.kind() == "dotted_name" && ctx.text(name).split('.').next() == Some("torch")
+            });
+            found
+        });
+    found
+}
+
+/// Whether `node` assigns or imports the name `device`.
+fn binds_device(ctx: &RuleContext, node: Node) -> bool {
+    let is_device = |target: Node| target.kind() == "identifier" && ctx.text(target) == "device";
+    match node.kind() {
+        "assignment" | "augmented_assignment" | "for_statement" => node.child_by_field_name("left").is_some_and(|left| {
+            let mut cursor = left.walk();
+            let found = is_device(left)
+                || (matches!(left.kind(), "pattern_list" | "tuple_pattern" | "list_pattern")
+                    && left.named_children(&mut cursor).any(is_device));
+            found
+        }),
+        "import_statement" | "import_from_statement" => {
+            let mut cursor = node.walk();
+            let found = node.children_by_field_name("name", &mut cursor).any(|name| {
+                let bound = match name.kind() {
+                    "aliased_import" => name.child_by_field_name("alias"),
+                    _ => Some(name),
+                };
+                bound.is_some_and(|bound| ctx.text(bound) == "device")
+            });
+            found
+        }
+        _ => false,
+    }
+}
+
+/// TG011: moving a tensor or module that is already on the device again.
+pub struct RedundantTransfer;
+
+impl Rule for RedundantTransfer {
+    fn id(&self) -> &'static str {
+        "TG011"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Value moved to the device twice"
+    }
+
+    fn category(&self) -> &'static str {
+        "GPU Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            (#match? @method "^(to|cuda)$")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let object = captures.get("object")?;
+        if !moves_to_device(target, ctx.code) || !ctx.dataflow(target).value_of(object).is_some_and(|value| value.on_device()) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            &format!("{} is already on the device; this transfer is redundant", ctx.text(object)),
+            None,
+        ))
+    }
+}
@@ -0,0 +1,347 @@
+use tree_sitter::Node;
+
+use super::{
+    enclosing_training_loop, forward_passes, inference, insert_line_before, unsafe_fix, without_grad, Captures, Inference,
+    Rule, RuleContext, ANY_CALL,
+};
+use crate::analyzer::{scope, Edit, Finding, Severity, TrainingLoop};
+
+/// TG002: gradients are tracked unless inference runs under `torch.no_grad()`.
+pub struct MissingNoGrad;
+
+impl Rule for MissingNoGrad {
+    fn id(&self) -> &'static str {
+        "TG002"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Gradients tracked outside `torch.no_grad()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Memory Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        ANY_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let model = ctx.dataflow(target).forward_model(target)?;
+        let inference = inference(ctx, target)?;
+        if without_grad(ctx, target) {
+            return None;
+        }
+        // Report the first forward pass tracking gradients, not every one.
+        let repeated = forward_passes(ctx, inference.body())
+            .into_iter()
+            .take_while(|(call, _)| call.start_byte() < target.start_byte())
+            .any(|(call, _)| !without_grad(ctx, call));
+        if repeated {
+            return None;
+        }
+        let (place, fix) = match inference {
+            Inference::Function(function) => {
+                let name = ctx.text(function.child_by_field_name("name")?);
+                let fix = unsafe_fix(
+                    "Decorate with @torch.inference_mode()",
+                    vec![insert_line_before(ctx, function, "@torch.inference_mode()")],
+                );
+                (format!("{}()", name), Some(fix))
+            }
+            Inference::Loop(_) => ("an evaluation loop".to_string(), None),
+        };
+        Some(self.report(
+            ctx,
+            target,
+            &format!(
+                "{} builds an autograd graph in {} that is never used; run inference under torch.inference_mode(), or torch.no_grad() if the outputs are used in autograd later",
+                model, place
+            ),
+            fix,
+        ))
+    }
+}
+
+/// TG022: adding a graph-attached tensor to a running total keeps the graph
+/// of every iteration alive.
+pub struct GraphAccumulation;
+
+impl Rule for GraphAccumulation {
+    fn id(&self) -> &'static str {
+        "TG022"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Tensor with autograd history accumulated across training steps"
+    }
+
+    fn category(&self) -> &'static str {
+        "Memory Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (augmented_assignment
+            left: (_) @name
+            right: (_) @value) @target
+
+        (assignment
+            left: (_) @name
+            right: (binary_operator
+                left: (_) @same
+                right: (_) @value)
+            (#eq? @name @same)) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let name = captures.get("name")?;
+        let value = captures.get("value")?;
+        if !matches!(name.kind(), "identifier" | "attribute" | "subscript") {
+            return None;
+        }
+        let training = retained_across_steps(ctx, target, value)?;
+        let name = ctx.text(name);
+        if reassigned(ctx, training.batch_loop, name) || backpropagated(ctx, target, name) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            &format!(
+                "{} keeps the autograd graph of every step alive; accumulate {} instead",
+                name,
+                detached(ctx, value)
+            ),
+            Some(unsafe_fix("Detach before accumulating", vec![Edit::replace(ctx.code, value, detached(ctx, value))])),
+        ))
+    }
+}
+
+/// TG023: `retain_graph=True` in a loop keeps each iteration's graph until
+/// the next one replaces it, and usually hides a second backward pass that
+/// could be a single one.
+pub struct RetainGraphInLoop;
+
+impl Rule for RetainGraphInLoop {
+    fn id(&self) -> &'static str {
+        "TG023"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`retain_graph=True` inside a loop"
+    }
+
+    fn category(&self) -> &'static str {
+        "Memory Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (keyword_argument
+            name: (identifier) @name
+            value: (true)
+            (#eq? @name "retain_graph")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let body = scope::enclosing_loop(target)?;
+        // A later backward pass in the same iteration needs the graph kept.
+        let backward_again = scope::descendants(body)
+            .any(|n| n.start_byte() >= target.end_byte() && (is_backward(ctx, n) || is_autograd_grad(ctx, n)));
+        if backward_again {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "retain_graph=True keeps the graph alive although nothing backpropagates through it again in this iteration; remove it, or sum the losses and call backward() once",
+            None,
+        ))
+    }
+}
+
+/// TG024: appending graph-attached outputs to a list during training keeps
+/// every step's graph alive until the list is dropped.
+pub struct OutputsInList;
+
+impl Rule for OutputsInList {
+    fn id(&self) -> &'static str {
+        "TG024"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Tensor with autograd history stored in a list during training"
+    }
+
+    fn category(&self) -> &'static str {
+        "Memory Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @list
+                attribute: (identifier) @method)
+            arguments: (argument_list . (_) @value .)
+            (#match? @method "^(append|extend)$")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let value = captures.get("value")?;
+        let training = retained_across_steps(ctx, target, value)?;
+        let list = ctx.text(captures.get("list")?);
+        if reassigned(ctx, training.batch_loop, list) || backpropagated(ctx, target, list) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            &format!(
+                "{} keeps the autograd graph of every step alive; store {}",
+                list,
+                detached(ctx, value)
+            ),
+            Some(unsafe_fix("Detach before storing", vec![Edit::replace(ctx.code, value, detached(ctx, value))])),
+        ))
+    }
+}
+
+/// TG026: a prediction returned with its autograd graph keeps the graph
+/// alive for as long as the caller holds on to it.
+pub struct GradientsReturned;
+
+impl Rule for GradientsReturned {
+    fn id(&self) -> &'static str {
+        "TG026"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Inference function returns a tensor that requires grad"
+    }
+
+    fn category(&self) -> &'static str {
+        "Memory Usage"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        "(return_statement) @target"
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let Inference::Function(function) = inference(ctx, target)? else { return None };
+        // Outputs may be computed under no_grad and returned after the block.
+        if scope::mentions(function, ctx.code, |id| matches!(id, "no_grad" | "inference_mode" | "set_grad_enabled")) {
+            return None;
+        }
+        let returned = target.named_child(0)?;
+        let mut cursor = returned.walk();
+        let values: Vec<Node> = match returned.kind() {
+            "expression_list" | "tuple" | "list" => returned.named_children(&mut cursor).collect(),
+            _ => vec![returned],
+        };
+        let value = values.into_iter().find(|value| ctx.dataflow(*value).value_of(*value).is_some_and(|v| v.has_graph()))?;
+        let name = ctx.text(function.child_by_field_name("name")?);
+        Some(self.report(
+            ctx,
+            value,
+            &format!(
+                "{}() returns {} with its autograd graph; decorate {} with @torch.inference_mode() or return {}",
+                name,
+                ctx.text(value),
+                name,
+                detached(ctx, value)
+            ),
+            None,
+        ))
+    }
+}
+
+/// The training loop in which `value`, a tensor attached to a model's graph,
+/// is kept by `node` with gradients enabled.
+fn retained_across_steps<'tree>(ctx: &RuleContext, node: Node<'tree>, value: Node) -> Option<TrainingLoop<'tree>> {
+    if !ctx.dataflow(value).value_of(value).is_some_and(|value| value.has_graph()) || without_grad(ctx, node) {
+        return None;
+    }
+    enclosing_training_loop(ctx, node)
+}
+
+/// Whether `name` is bound afresh in `batch_loop`, by an assignment not
+/// computed from its previous value, so it only lives for one iteration.
+fn reassigned(ctx: &RuleContext, batch_loop: Node, name: &str) -> bool {
+    scope::descendants(batch_loop).any(|n| {
+        n.kind() == "assignment"
+            && n.child_by_field_name("left").is_some_and(|left| ctx.text(left) == name)
+            && n.child_by_field_name("right").is_some_and(|right| !refers_to(ctx, right, name))
+    })
+}
+
+/// Whether `name` occurs in `expr`.
+fn refers_to(ctx: &RuleContext, expr: Node, name: &str) -> bool {
+    std::iter::once(expr).chain(scope::all_descendants(expr)).any(|n| ctx.text(n) == name)
+}
+
+/// Whether a backward pass in the function of `node` goes through `name`,
+/// as when losses are accumulated on purpose and backpropagated together.
+fn backpropagated(ctx: &RuleContext, node: Node, name: &str) -> bool {
+    scope::descendants(scope::function_scope(node)).any(|call| {
+        is_backward(ctx, call)
+            && call
+                .child_by_field_name("function")
+                .and_then(|function| function.child_by_field_name("object"))
+                .is_some_and(|object| refers_to(ctx, object, name))
+    })
+}
+
+fn is_backward(ctx: &RuleContext, node: Node) -> bool {
+    node.kind() == "call"
+        && node
+            .child_by_field_name("function")
+            .and_then(|function| function.child_by_field_name("attribute"))
+            .is_some_and(|method| ctx.text(method) == "backward")
+}
+
+fn is_autograd_grad(ctx: &RuleContext, node: Node) -> bool {
+    node.kind() == "call" && node.child_by_field_name("function").is_some_and(|function| ctx.text(function) == "torch.autograd.grad")
+}
+
+/// `expr` with a method call appended, parenthesized unless it is primary.
+fn with_method(ctx: &RuleContext, expr: Node, method: &str) -> String {
+    match expr.kind() {
+        "identifier" | "attribute" | "call" | "subscript" | "parenthesized_expression" => format!("{}.{}()", ctx.text(expr), method),
+        _ => format!("({}).{}()", ctx.text(expr), method),
+    }
+}
+
+fn detached(ctx: &RuleContext, expr: Node) -> String {
+    with_method(ctx, expr, "detach")
+}
@@ -0,0 +1,396 @@
+use tree_sitter::{Node, Query, QueryCapture};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::dataflow::Dataflow;
+use super::project::{self, ProjectView};
+use super::{scope, Applicability, Edit, Finding, Fix, LoopKind, Severity, Span, TorchVersion, TrainingLoop};
+
+pub mod custom;
+pub mod dataloader;
+pub mod gpu;
+pub mod memory;
+pub mod model_state;
+pub mod performance;
+pub mod security;
+pub mod sync;
+pub mod training;
+
+/// Matches every `<expr>.backward()` call. Shared by the rules that reason
+/// about the backward pass.
+pub(crate) const BACKWARD_CALL: &str = r#"
+    (call
+        function: (attribute attribute: (identifier) @method)
+        (#eq? @method "backward")) @target
+"#;
+
+/// Matches every call. Rules about inference narrow it to forward passes
+/// with [`forward_passes`] or `Dataflow::forward_model`.
+pub(crate) const ANY_CALL: &str = "(call) @target";
+
+const RULES_DOC_URL: &str = "https://github.com/richiectr360/TorchGuard/blob/main/docs/rules.md";
+
+/// Link to the documentation of rule `id`.
+pub fn doc_url(id: &str) -> String {
+    format!("{}#{}", RULES_DOC_URL, id.to_lowercase())
+}
+
+/// Source of the file being analyzed.
+pub struct RuleContext<'a> {
+    pub code: &'a str,
+    /// PyTorch release the project targets, when configured.
+    pub torch_version: Option<TorchVersion>,
+    /// Functions of this module and, in project mode, of the other modules.
+    pub project: ProjectView<'a>,
+    /// Def-use facts, computed once per function on first use.
+    pub(crate) dataflow: RefCell<HashMap<usize, Rc<Dataflow<'a>>>>,
+}
+
+impl<'a> RuleContext<'a> {
+    pub fn text(&self, node: Node) -> &'a str {
+        node.utf8_text(self.code.as_bytes()).unwrap_or("")
+    }
+
+    /// Def-use facts for the function, or module-level code, containing `node`.
+    pub fn dataflow(&self, node: Node) -> Rc<Dataflow<'a>> {
+        let scope = scope::function_scope(node);
+        self.dataflow
+            .borrow_mut()
+            .entry(scope.id())
+            .or_insert_with(|| Rc::new(Dataflow::new(scope, self.code)))
+            .clone()
+    }
+
+    /// Whether a function called from `scope`, directly or through further
+    /// calls and across modules, mentions an identifier satisfying `pred`.
+    pub fn reaches(&self, scope: Node, pred: impl Fn(&str) -> bool) -> bool {
+        let callees = scope::descendants(scope)
+            .filter(|n| n.kind() == "call")
+            .filter_map(|call| project::callee_path(call, self.code));
+        let class = scope::enclosing_class(scope)
+            .and_then(|class| class.child_by_field_name("name"))
+            .map(|name| self.text(name));
+        self.project.reaches(callees, class, pred)
+    }
+}
+
+/// Captures of a single query match, looked up by capture name.
+pub struct Captures<'a, 'tree> {
+    query: &'a Query,
+    captures: &'a [QueryCapture<'tree>],
+}
+
+impl<'a, 'tree> Captures<'a, 'tree> {
+    pub fn new(query: &'a Query, captures: &'a [QueryCapture<'tree>]) -> Self {
+        Captures { query, captures }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Node<'tree>> {
+        let index = self.query.capture_index_for_name(name)?;
+        self.captures
+            .iter()
+            .find(|capture| capture.index == index)
+            .map(|capture| capture.node)
+    }
+
+    /// The node a finding is anchored to. Every rule query captures it as `@target`.
+    pub fn target(&self) -> Option<Node<'tree>> {
+        self.get("target")
+    }
+}
+
+/// A single self-contained check.
+///
+/// The analyzer runs `query()` over the syntax tree and calls `check` once per
+/// match; the rule decides whether the match is a finding.
+pub trait Rule: Send + Sync {
+    /// Stable code used in output, suppressions and configuration. Never reuse one.
+    fn id(&self) -> &str;
+    /// One-line description, as listed in docs/rules.md.
+    fn summary(&self) -> &str;
+    fn category(&self) -> &str;
+    fn severity(&self) -> Severity;
+    fn query(&self) -> &str;
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding>;
+
+    fn doc_url(&self) -> String {
+        doc_url(self.id())
+    }
+
+    /// Builds a finding for this rule anchored at `node`.
+    fn report(&self, ctx: &RuleContext, node: Node, message: &str, fix: Option<Fix>) -> Finding {
+        let span = Span::from_node(ctx.code, node);
+        Finding {
+            rule_id: self.id().to_string(),
+            category: self.category().to_string(),
+            message: message.to_string(),
+            line: span.start_line as i32,
+            span,
+            severity: self.severity(),
+            doc_url: self.doc_url(),
+            fix,
+            cell: None,
+        }
+    }
+}
+
+pub(crate) fn fix(description: &str, edits: Vec<Edit>) -> Fix {
+    Fix {
+        description: description.to_string(),
+        edits,
+        applicability: Applicability::Safe,
+    }
+}
+
+pub(crate) fn unsafe_fix(description: &str, edits: Vec<Edit>) -> Fix {
+    Fix {
+        applicability: Applicability::Unsafe,
+        ..fix(description, edits)
+    }
+}
+
+/// The statement containing `node`, i.e. its ancestor directly inside a block or module.
+pub(crate) fn statement(node: Node) -> Node {
+    std::iter::once(node)
+        .chain(scope::ancestors(node))
+        .find(|n| n.parent().is_none_or(|p| matches!(p.kind(), "block" | "module")))
+        .unwrap_or(node)
+}
+
+pub(crate) fn line_start(code: &str, byte: usize) -> usize {
+    code[..byte].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Leading whitespace of the line `node` starts on.
+pub(crate) fn indentation<'a>(ctx: &RuleContext<'a>, node: Node) -> &'a str {
+    let start = line_start(ctx.code, node.start_byte());
+    let line = &ctx.code[start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// The file's line ending: `\r\n` if it uses any, otherwise `\n`.
+pub(crate) fn newline(code: &str) -> &'static str {
+    if code.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Whether `node` is the first thing on its line, unlike the statements of a
+/// body written after the colon, as in `for x in xs: f(x); g(x)`.
+pub(crate) fn starts_line(ctx: &RuleContext, node: Node) -> bool {
+    ctx.code[line_start(ctx.code, node.start_byte())..node.start_byte()].trim().is_empty()
+}
+
+/// Inserts `line` above the statement containing `node`, at the same
+/// indentation, or just before it, separated by `;`, when the statement
+/// shares its line with others.
+pub(crate) fn insert_line_before(ctx: &RuleContext, node: Node, line: &str) -> Edit {
+    let statement = statement(node);
+    if !starts_line(ctx, statement) {
+        return Edit::insert(ctx.code, statement.start_byte(), format!("{}; ", line));
+    }
+    let at = line_start(ctx.code, statement.start_byte());
+    Edit::insert(ctx.code, at, format!("{}{}{}", indentation(ctx, statement), line, newline(ctx.code)))
+}
+
+/// Inserts `line` after the module's leading imports, or at the top of the
+/// file. A module docstring, comments and `from __future__` imports before
+/// the imports are skipped, so the line never lands above them.
+pub(crate) fn insert_line_after_imports(ctx: &RuleContext, node: Node, line: &str) -> Edit {
+    let root = scope::ancestors(node).last().unwrap_or(node);
+    let mut cursor = root.walk();
+    let last_import = root
+        .children(&mut cursor)
+        .enumerate()
+        .take_while(|(i, n)| {
+            matches!(
+                n.kind(),
+                "import_statement" | "import_from_statement" | "future_import_statement" | "comment"
+            ) || (*i == 0 && is_docstring(*n))
+        })
+        .map(|(_, n)| n)
+        .filter(|n| n.kind() != "comment")
+        .last();
+    let at = match last_import {
+        Some(import) => ctx.code[import.end_byte()..]
+            .find('\n')
+            .map_or(ctx.code.len(), |i| import.end_byte() + i + 1),
+        None => 0,
+    };
+    let newline = newline(ctx.code);
+    let prefix = if at == ctx.code.len() && !ctx.code.is_empty() && !ctx.code.ends_with('\n') {
+        newline
+    } else {
+        ""
+    };
+    Edit::insert(ctx.code, at, format!("{}{}{}", prefix, line, newline))
+}
+
+/// Whether `node` is a statement made of a single string literal.
+fn is_docstring(node: Node) -> bool {
+    node.kind() == "expression_statement" && node.named_child_count() == 1 && node.named_child(0).is_some_and(|n| n.kind() == "string")
+}
+
+/// The value of keyword argument `name` in `arguments`, an argument list.
+pub(crate) fn keyword_argument<'tree>(ctx: &RuleContext, arguments: Node<'tree>, name: &str) -> Option<Node<'tree>> {
+    let mut cursor = arguments.walk();
+    let argument = arguments.named_children(&mut cursor).find(|n| {
+        n.kind() == "keyword_argument" && n.child_by_field_name("name").is_some_and(|key| ctx.text(key) == name)
+    });
+    argument?.child_by_field_name("value")
+}
+
+/// Appends `argument` to `arguments`, an argument list, after its last
+/// argument. `None` when the list is empty.
+pub(crate) fn append_argument(ctx: &RuleContext, arguments: Node, argument: &str) -> Option<Edit> {
+    let mut cursor = arguments.walk();
+    let last = arguments.named_children(&mut cursor).filter(|n| n.kind() != "comment").last()?;
+    Some(Edit::insert(ctx.code, last.end_byte(), format!(", {}", argument)))
+}
+
+/// The innermost loop over a DataLoader around `node` within its function.
+pub(crate) fn enclosing_loader_loop<'tree>(ctx: &RuleContext, node: Node<'tree>) -> Option<TrainingLoop<'tree>> {
+    scope::ancestors(node)
+        .take_while(|n| !matches!(n.kind(), "function_definition" | "class_definition"))
+        .find_map(|ancestor| TrainingLoop::recognize(ctx, ancestor))
+}
+
+/// The training loop whose batch loop runs `node`: the innermost loop over a
+/// DataLoader around `node` within its function, if that loop trains.
+pub(crate) fn enclosing_training_loop<'tree>(ctx: &RuleContext, node: Node<'tree>) -> Option<TrainingLoop<'tree>> {
+    enclosing_loader_loop(ctx, node).filter(|found| found.kind == LoopKind::Training)
+}
+
+/// Whether `node` runs under `torch.no_grad()`, `torch.inference_mode()` or
+/// `torch.set_grad_enabled(False)`, as a context manager or as a decorator
+/// of its function.
+pub(crate) fn without_grad(ctx: &RuleContext, node: Node) -> bool {
+    let disables_grad =
+        |text: &str| text.contains("no_grad") || text.contains("inference_mode") || text.contains("set_grad_enabled(False)");
+    scope::ancestors(node).any(|ancestor| match ancestor.kind() {
+        "with_statement" => {
+            let mut cursor = ancestor.walk();
+            let clause = ancestor.children(&mut cursor).find(|n| n.kind() == "with_clause");
+            clause.is_some_and(|clause| disables_grad(ctx.text(clause)))
+        }
+        "decorated_definition" => {
+            let mut cursor = ancestor.walk();
+            let found = ancestor.children(&mut cursor).any(|n| n.kind() == "decorator" && disables_grad(ctx.text(n)));
+            found
+        }
+        _ => false,
+    })
+}
+
+/// Words that mark a function as evaluation or inference code, matched
+/// against the `_`-separated parts of its name.
+const INFERENCE_WORDS: &[&str] = &[
+    "eval", "evaluate", "evaluation", "val", "valid", "validate", "validation", "predict", "prediction", "infer",
+    "inference",
+];
+
+/// Lightning hooks, which the trainer already runs in eval mode without grad.
+const LIGHTNING_HOOKS: &[&str] = &["validation_step", "test_step", "predict_step"];
+
+/// Where inference runs.
+pub(crate) enum Inference<'tree> {
+    /// A function named for evaluation or inference, such as `evaluate`,
+    /// `val_epoch`, `predict` or a bare `test` (not pytest's `test_*`).
+    Function(Node<'tree>),
+    /// An evaluation loop over a DataLoader in any other function.
+    Loop(TrainingLoop<'tree>),
+}
+
+impl<'tree> Inference<'tree> {
+    /// The code running inference.
+    pub(crate) fn body(&self) -> Node<'tree> {
+        match self {
+            Inference::Function(function) => *function,
+            Inference::Loop(found) => found.batch_loop,
+        }
+    }
+}
+
+/// Where `node` runs inference, if it does. Functions that call
+/// `backward()` or `optimizer.step()` are training code whatever their
+/// name, though they may contain an evaluation loop.
+pub(crate) fn inference<'tree>(ctx: &RuleContext, node: Node<'tree>) -> Option<Inference<'tree>> {
+    let function = scope::enclosing_function(node).filter(|function| function.kind() == "function_definition");
+    if let Some(function) = function {
+        let name = function.child_by_field_name("name").map_or("", |name| ctx.text(name)).to_lowercase();
+        let named = name == "test" || name.split('_').any(|word| INFERENCE_WORDS.contains(&word));
+        let trains = scope::calls(function, ctx.code, "backward") || optimizer_step(ctx, function).is_some();
+        if LIGHTNING_HOOKS.contains(&name.as_str()) {
+            return None;
+        }
+        if named && !trains {
+            return Some(Inference::Function(function));
+        }
+    }
+    enclosing_loader_loop(ctx, node).filter(|found| found.kind == LoopKind::Evaluation).map(Inference::Loop)
+}
+
+/// The forward passes in `scope`, in source order, with the model each runs.
+pub(crate) fn forward_passes<'tree>(ctx: &RuleContext, scope: Node<'tree>) -> Vec<(Node<'tree>, String)> {
+    scope::descendants(scope)
+        .filter(|n| n.kind() == "call")
+        .filter_map(|call| Some((call, ctx.dataflow(call).forward_model(call)?)))
+        .collect()
+}
+
+/// The first `<optimizer>.step()` call in `scope`.
+pub(crate) fn optimizer_step<'tree>(ctx: &RuleContext, scope: Node<'tree>) -> Option<Node<'tree>> {
+    scope::descendants(scope).find(|n| {
+        let Some(function) = n.child_by_field_name("function").filter(|_| n.kind() == "call") else {
+            return false;
+        };
+        let object = function.child_by_field_name("object");
+        let method = function.child_by_field_name("attribute");
+        matches!((object, method), (Some(o), Some(m)) if ctx.text(m) == "step" && ctx.dataflow(o).is_optimizer(o))
+    })
+}
+
+/// All built-in rules, in reporting order.
+pub fn registry() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(gpu::RawCuda),
+        Box::new(memory::MissingNoGrad),
+        Box::new(training::MissingZeroGrad),
+        Box::new(performance::DataLoaderWorkers),
+        Box::new(performance::BatchSizePowerOfTwo),
+        Box::new(performance::MixedPrecision),
+        Box::new(model_state::MissingEval),
+        Box::new(training::GradientClipping),
+        Box::new(training::LrScheduler),
+        Box::new(training::StepWithoutBackward),
+        Box::new(gpu::RedundantTransfer),
+        Box::new(training::ZeroGradAfterBackward),
+        Box::new(training::SchedulerBeforeOptimizer),
+        Box::new(training::MissingTrainMode),
+        Box::new(security::UnsafeTorchLoad),
+        Box::new(security::PickleLoad),
+        Box::new(security::TrustedHubRepo),
+        Box::new(security::EvalOnStrings),
+        Box::new(sync::TensorToHost),
+        Box::new(sync::TensorInPython),
+        Box::new(sync::CudaSynchronize),
+        Box::new(memory::GraphAccumulation),
+        Box::new(memory::RetainGraphInLoop),
+        Box::new(memory::OutputsInList),
+        Box::new(model_state::TrainInEvalLoop),
+        Box::new(memory::GradientsReturned),
+        Box::new(dataloader::MissingPinMemory),
+        Box::new(dataloader::WorkersNotPersistent),
+        Box::new(dataloader::ShuffledEvaluation),
+        Box::new(dataloader::BatchNormWithoutDropLast),
+        Box::new(dataloader::PrefetchWithoutWorkers),
+        Box::new(dataloader::LambdaInWorkers),
+    ]
+}
+
+#[cfg(test)]
+mod tests;
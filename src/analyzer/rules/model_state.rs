@@ -0,0 +1,143 @@
+use tree_sitter::Node;
+
+use super::{
+    enclosing_loader_loop, forward_passes, inference, insert_line_before, unsafe_fix, Captures, Rule, RuleContext, ANY_CALL,
+};
+use crate::analyzer::{scope, Finding, LoopKind, Severity};
+
+/// TG007: dropout and batch norm behave differently until `model.eval()` is called.
+pub struct MissingEval;
+
+impl Rule for MissingEval {
+    fn id(&self) -> &'static str {
+        "TG007"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Inference without `model.eval()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Model State"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        ANY_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let model = ctx.dataflow(target).forward_model(target)?;
+        let inference = inference(ctx, target)?;
+        // The last mode switch before the forward pass decides the mode.
+        let function = scope::function_scope(target);
+        let last_switch = scope::descendants(function)
+            .take_while(|n| n.start_byte() < target.start_byte())
+            .filter_map(|n| Some((n, mode_switch(ctx, n, &model)?)))
+            .last();
+        match last_switch {
+            Some((_, true)) => return None,
+            // `model.train()` in the same evaluation loop is TG025's to report.
+            Some((switch, false)) if same_loop(ctx, switch, target) => return None,
+            _ => {}
+        }
+        // Report the first forward pass in train mode, not every one.
+        let since = last_switch.map_or(0, |(switch, _)| switch.end_byte());
+        let repeated = forward_passes(ctx, inference.body())
+            .into_iter()
+            .take_while(|(call, _)| call.start_byte() < target.start_byte())
+            .any(|(call, other)| other == model && call.start_byte() >= since);
+        if repeated {
+            return None;
+        }
+        // Outside the batch loop, so the mode is set once.
+        let anchor = enclosing_loader_loop(ctx, target).map_or(target, |found| found.batch_loop);
+        Some(self.report(
+            ctx,
+            target,
+            &format!("{} runs inference in training mode; call {}.eval() first", model, model),
+            Some(unsafe_fix(
+                "Add model.eval() before inference",
+                vec![insert_line_before(ctx, anchor, &format!("{}.eval()", model))],
+            )),
+        ))
+    }
+}
+
+/// TG025: `model.train()` in an evaluation loop re-enables dropout and batch
+/// norm updates for the batches that follow.
+pub struct TrainInEvalLoop;
+
+impl Rule for TrainInEvalLoop {
+    fn id(&self) -> &'static str {
+        "TG025"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`model.train()` inside an evaluation loop"
+    }
+
+    fn category(&self) -> &'static str {
+        "Model State"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @model
+                attribute: (identifier) @method)
+            (#eq? @method "train")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let model = ctx.text(captures.get("model")?);
+        if mode_switch(ctx, target, model)? {
+            return None;
+        }
+        let found = enclosing_loader_loop(ctx, target)?;
+        if found.kind != LoopKind::Evaluation {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            &format!(
+                "{}.train() inside an evaluation loop evaluates the remaining batches in training mode; switch modes before or after the loop",
+                model
+            ),
+            None,
+        ))
+    }
+}
+
+fn same_loop(ctx: &RuleContext, a: Node, b: Node) -> bool {
+    match (enclosing_loader_loop(ctx, a), enclosing_loader_loop(ctx, b)) {
+        (Some(a), Some(b)) => a.batch_loop == b.batch_loop,
+        _ => false,
+    }
+}
+
+/// Whether `node` switches `model` to eval mode (`true`) or training mode:
+/// `model.eval()`, `model.train()` or `model.train(False)`.
+fn mode_switch(ctx: &RuleContext, node: Node, model: &str) -> Option<bool> {
+    let function = node.child_by_field_name("function").filter(|_| node.kind() == "call")?;
+    if ctx.text(function.child_by_field_name("object")?) != model {
+        return None;
+    }
+    match ctx.text(function.child_by_field_name("attribute")?) {
+        "eval" => Some(true),
+        "train" => Some(node.child_by_field_name("arguments").is_some_and(|args| ctx.text(args).contains("False"))),
+        _ => None,
+    }
+}
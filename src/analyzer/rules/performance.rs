@@ -0,0 +1,205 @@
+use tree_sitter::Node;
+
+use super::dataloader::DATALOADER_CALL;
+use super::{
+    indentation, insert_line_before, line_start, newline, optimizer_step, starts_line, statement, unsafe_fix, Captures,
+    Rule, RuleContext, BACKWARD_CALL,
+};
+use crate::analyzer::{scope, Edit, Finding, Fix, Severity, Span};
+
+/// TG004: without worker processes, data loading blocks the training step.
+pub struct DataLoaderWorkers;
+
+impl Rule for DataLoaderWorkers {
+    fn id(&self) -> &'static str {
+        "TG004"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`DataLoader` without `num_workers`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        DATALOADER_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let arguments = captures.get("arguments")?;
+        if scope::mentions(arguments, ctx.code, |id| id == "num_workers") {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "Set num_workers in DataLoader for faster data loading",
+            None,
+        ))
+    }
+}
+
+/// TG005: GPU kernels are tuned for power-of-two batch dimensions.
+pub struct BatchSizePowerOfTwo;
+
+impl Rule for BatchSizePowerOfTwo {
+    fn id(&self) -> &'static str {
+        "TG005"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Batch size is not a power of two"
+    }
+
+    fn category(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (keyword_argument
+            name: (identifier) @name
+            value: (integer) @value
+            (#eq? @name "batch_size")) @target
+        (assignment
+            left: (identifier) @name
+            right: (integer) @value
+            (#eq? @name "batch_size")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let size: i64 = ctx.text(captures.get("value")?).parse().ok()?;
+        if size <= 0 || size & (size - 1) == 0 {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            captures.target()?,
+            "Consider using a power of 2 for batch size for optimal GPU utilization",
+            None,
+        ))
+    }
+}
+
+/// TG006: autocast and gradient scaling roughly halve memory and speed up training.
+pub struct MixedPrecision;
+
+impl Rule for MixedPrecision {
+    fn id(&self) -> &'static str {
+        "TG006"
+    }
+
+    fn summary(&self) -> &'static str {
+        "No mixed precision training"
+    }
+
+    fn category(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        BACKWARD_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let function = scope::function_scope(target);
+        if scope::mentions(function, ctx.code, |id| matches!(id, "amp" | "autocast" | "GradScaler")) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "Consider using mixed precision training with torch.cuda.amp",
+            mixed_precision_fix(ctx, target),
+        ))
+    }
+}
+
+/// Scales the loss with a `GradScaler` created before the training loop, steps
+/// the optimizer through the scaler, and runs the statements computing the loss
+/// under autocast. Only offered for a backward pass inside a loop that also
+/// calls `optimizer.step()` itself.
+fn mixed_precision_fix(ctx: &RuleContext, backward: Node) -> Option<Fix> {
+    let training_loop = scope::enclosing_loop(backward)?;
+    let loss = backward.child_by_field_name("function")?.child_by_field_name("object")?;
+    let arguments = backward.child_by_field_name("arguments")?;
+
+    let mut edits = vec![
+        insert_line_before(ctx, training_loop, "scaler = torch.cuda.amp.GradScaler()"),
+        Edit::replace(
+            ctx.code,
+            backward,
+            format!("scaler.scale({}).backward{}", ctx.text(loss), ctx.text(arguments)),
+        ),
+    ];
+
+    // Scaled gradients must be unscaled by `scaler.step`; when the step is out
+    // of reach, e.g. in a helper, scaling the loss alone would corrupt training.
+    let step = optimizer_step(ctx, training_loop).filter(|step| statement(*step).named_child(0) == Some(*step))?;
+    let optimizer = step.child_by_field_name("function")?.child_by_field_name("object")?;
+    let separator = if starts_line(ctx, step) {
+        format!("{}{}", newline(ctx.code), indentation(ctx, step))
+    } else {
+        "; ".to_string()
+    };
+    edits.push(Edit::replace(
+        ctx.code,
+        step,
+        format!("scaler.step({}){}scaler.update()", ctx.text(optimizer), separator),
+    ));
+
+    // A body on the loop's own line cannot take a `with` block.
+    if let Some((first, last)) = loss_computation(ctx, statement(backward), loss).filter(|(first, _)| starts_line(ctx, *first)) {
+        let indent = indentation(ctx, first);
+        let start = line_start(ctx.code, first.start_byte());
+        let body: Vec<String> = ctx.code[start..last.end_byte()]
+            .lines()
+            .map(|line| if line.trim().is_empty() { line.to_string() } else { format!("    {}", line) })
+            .collect();
+        edits.push(Edit {
+            span: Span::from_bytes(ctx.code, start, last.end_byte()),
+            replacement: format!("{}with torch.autocast(device_type='cuda'):{}{}", indent, newline(ctx.code), body.join(newline(ctx.code))),
+        });
+    }
+
+    Some(unsafe_fix("Add mixed precision training", edits))
+}
+
+/// The run of assignment statements just before `backward_statement` that ends
+/// with the one assigning `loss`.
+fn loss_computation<'tree>(ctx: &RuleContext, backward_statement: Node<'tree>, loss: Node) -> Option<(Node<'tree>, Node<'tree>)> {
+    let is_assignment = |n: &Node| n.kind() == "expression_statement" && n.named_child(0).is_some_and(|c| c.kind() == "assignment");
+
+    let mut assignments = Vec::new();
+    let mut previous = backward_statement.prev_named_sibling();
+    while let Some(node) = previous.filter(is_assignment) {
+        assignments.push(node);
+        previous = node.prev_named_sibling();
+    }
+
+    let assigns_loss = |n: &Node| {
+        n.named_child(0)
+            .and_then(|a| a.child_by_field_name("left"))
+            .is_some_and(|left| ctx.text(left) == ctx.text(loss))
+    };
+    let last = *assignments.iter().find(|n| assigns_loss(n))?;
+    let first = *assignments.last()?;
+    Some((first, last))
+}
@@ -0,0 +1,285 @@
+use tree_sitter::Node;
+
+use super::{append_argument, fix, keyword_argument, Captures, Rule, RuleContext};
+use crate::analyzer::{scope, Edit, Finding, Severity, TorchVersion};
+
+/// Words in a path or name that mark a saved model: extensions such as
+/// `.pt` and names such as `checkpoint`.
+const CHECKPOINT_WORDS: &[&str] = &["pt", "pth", "ckpt", "checkpoint", "checkpoints", "model", "weights"];
+
+/// Extensions of generic pickle files, which only count as saved models in
+/// code that uses torch.
+const PICKLE_WORDS: &[&str] = &["pkl", "pickle"];
+
+/// `torch.load` defaults to `weights_only=True` from this release on.
+const SAFE_LOAD_DEFAULT: TorchVersion = TorchVersion::new(2, 6);
+
+/// TG015: `torch.load` unpickles, so a tampered checkpoint runs code on load.
+pub struct UnsafeTorchLoad;
+
+impl Rule for UnsafeTorchLoad {
+    fn id(&self) -> &'static str {
+        "TG015"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`torch.load()` without `weights_only=True`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Security"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (identifier) @module
+                attribute: (identifier) @method)
+            arguments: (argument_list) @arguments
+            (#eq? @module "torch")
+            (#eq? @method "load")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let arguments = captures.get("arguments")?;
+        let weights_only = keyword_argument(ctx, arguments, "weights_only");
+        let edit = match weights_only {
+            Some(value) if ctx.text(value) == "False" => Edit::replace(ctx.code, value, "True"),
+            Some(_) => return None,
+            None if ctx.torch_version.is_some_and(|version| version >= SAFE_LOAD_DEFAULT) => return None,
+            None => append_argument(ctx, arguments, "weights_only=True")?,
+        };
+        // Restricting unpickling is only known to be harmless for state dicts.
+        let fix = loads_state_dict(ctx, target).then(|| fix("Load with weights_only=True", vec![edit]));
+        Some(self.report(
+            ctx,
+            target,
+            "torch.load() can run arbitrary code from the checkpoint; pass weights_only=True",
+            fix,
+        ))
+    }
+}
+
+/// TG016: pickle runs arbitrary code, whatever the file extension says. Only
+/// loads of what looks like a saved model are reported, judging by the path
+/// opened or the name loaded, so pickled data elsewhere stays quiet.
+pub struct PickleLoad;
+
+impl Rule for PickleLoad {
+    fn id(&self) -> &'static str {
+        "TG016"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Model loaded with `pickle` or `dill`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Security"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (identifier) @module
+                attribute: (identifier) @method)
+            (#match? @module "^(pickle|cPickle|dill|cloudpickle)$")
+            (#match? @method "^loads?$")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let module = ctx.text(captures.get("module")?);
+        let source = target.child_by_field_name("arguments")?.named_child(0)?;
+        if !is_checkpoint(ctx, target, loaded_path(ctx, target, source).unwrap_or(source)) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            &format!(
+                "{}.{}() can run arbitrary code; save weights as a state_dict and load them with torch.load(weights_only=True) or safetensors",
+                module,
+                ctx.text(captures.get("method")?)
+            ),
+            None,
+        ))
+    }
+}
+
+/// The path passed to `open()` for the file object `source`, as in
+/// `with open(path, "rb") as f` or `f = open(path, "rb")`, or directly in
+/// `pickle.load(open(path, "rb"))`.
+fn loaded_path<'tree>(ctx: &RuleContext, call: Node<'tree>, source: Node<'tree>) -> Option<Node<'tree>> {
+    let path_of = |open: Node<'tree>| open.child_by_field_name("arguments").and_then(|arguments| arguments.named_child(0));
+    let is_open = |n: Node| n.kind() == "call" && n.child_by_field_name("function").is_some_and(|f| ctx.text(f) == "open");
+    if is_open(source) {
+        return path_of(source);
+    }
+    if source.kind() != "identifier" {
+        return None;
+    }
+    let scope = scope::function_scope(call);
+    // The last binding before the call wins.
+    scope::all_descendants(scope)
+        .filter(|n| is_open(*n) && n.end_byte() <= call.start_byte())
+        .filter(|open| {
+            let bound = open.parent().and_then(|parent| match parent.kind() {
+                "as_pattern" => parent.child_by_field_name("alias"),
+                "assignment" => parent.child_by_field_name("left"),
+                _ => None,
+            });
+            bound.is_some_and(|bound| ctx.text(bound) == ctx.text(source))
+        })
+        .last()
+        .and_then(path_of)
+}
+
+/// Whether `path`, a path or the name of what is loaded, looks like a saved model.
+fn is_checkpoint(ctx: &RuleContext, call: Node, path: Node) -> bool {
+    let words: Vec<String> = ctx.text(path).split(|c: char| !c.is_ascii_alphanumeric()).map(str::to_lowercase).collect();
+    let has = |list: &[&str]| words.iter().any(|word| list.contains(&word.as_str()));
+    let module = scope::ancestors(call).last().unwrap_or(call);
+    has(CHECKPOINT_WORDS) || (has(PICKLE_WORDS) && scope::mentions(module, ctx.code, |id| id == "torch"))
+}
+
+/// TG017: `trust_repo=True` runs a hub repository's code without asking.
+pub struct TrustedHubRepo;
+
+impl Rule for TrustedHubRepo {
+    fn id(&self) -> &'static str {
+        "TG017"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`torch.hub.load()` with `trust_repo=True`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Security"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            arguments: (argument_list) @arguments
+            (#eq? @object "torch.hub")
+            (#eq? @method "load")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let trust_repo = keyword_argument(ctx, captures.get("arguments")?, "trust_repo")?;
+        if ctx.text(trust_repo) != "True" {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            trust_repo.parent().unwrap_or(target),
+            "trust_repo=True runs the repository's hubconf.py without confirmation; pin a reviewed repository and tag instead",
+            None,
+        ))
+    }
+}
+
+/// TG018: `eval`/`exec` on configuration text executes whatever it contains.
+pub struct EvalOnStrings;
+
+impl Rule for EvalOnStrings {
+    fn id(&self) -> &'static str {
+        "TG018"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`eval()` or `exec()` on a non-literal string"
+    }
+
+    fn category(&self) -> &'static str {
+        "Security"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (identifier) @name
+            arguments: (argument_list . (_) @source)
+            (#match? @name "^(eval|exec)$")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let source = captures.get("source")?;
+        if matches!(source.kind(), "string" | "concatenated_string") && !scope::all_descendants(source).any(|n| n.kind() == "interpolation") {
+            return None;
+        }
+        let name = ctx.text(captures.get("name")?);
+        Some(self.report(
+            ctx,
+            target,
+            &format!("{}() executes whatever the string contains; parse configuration with ast.literal_eval, json or yaml", name),
+            None,
+        ))
+    }
+}
+
+/// Whether the result of the `torch.load` call `load` is a state dict: it is
+/// passed to `load_state_dict()`, directly, through a subscript or through a
+/// variable, or assigned to a name mentioning `state_dict`.
+fn loads_state_dict(ctx: &RuleContext, load: Node) -> bool {
+    let mut node = load;
+    while let Some(parent) = node.parent().filter(|p| p.kind() == "subscript") {
+        node = parent;
+    }
+    let Some(parent) = node.parent() else { return false };
+    if parent.kind() == "argument_list" {
+        return parent.parent().is_some_and(|call| is_load_state_dict(ctx, call));
+    }
+    if parent.kind() != "assignment" {
+        return false;
+    }
+    let Some(name) = parent.child_by_field_name("left").filter(|left| left.kind() == "identifier").map(|left| ctx.text(left)) else {
+        return false;
+    };
+    name.contains("state_dict")
+        || scope::descendants(scope::function_scope(load)).any(|call| {
+            is_load_state_dict(ctx, call)
+                && call
+                    .child_by_field_name("arguments")
+                    .and_then(|args| args.named_child(0))
+                    .is_some_and(|arg| ctx.text(arg) == name)
+        })
+}
+
+fn is_load_state_dict(ctx: &RuleContext, call: Node) -> bool {
+    call.kind() == "call"
+        && call
+            .child_by_field_name("function")
+            .and_then(|function| function.child_by_field_name("attribute"))
+            .is_some_and(|method| ctx.text(method) == "load_state_dict")
+}
@@ -0,0 +1,205 @@
+//! Host-device synchronization inside a training loop. Each of these waits
+//! for every queued GPU kernel, so running them on every batch serializes the
+//! CPU and the GPU. Calls guarded by a modulo condition, as in
+//! `if step % 100 == 0:`, run every N steps and are not reported.
+
+use tree_sitter::Node;
+
+use super::{enclosing_training_loop, Captures, Rule, RuleContext};
+use crate::analyzer::{scope, Finding, Severity, TrainingLoop, Value};
+
+const ADVICE: &str = "accumulate on the device and read the value every N steps";
+
+/// The training loop `node` runs in on every iteration.
+fn per_iteration<'tree>(ctx: &RuleContext, node: Node<'tree>) -> Option<TrainingLoop<'tree>> {
+    let found = enclosing_training_loop(ctx, node)?;
+    let guarded = scope::ancestors(node).take_while(|n| *n != found.batch_loop).any(|n| {
+        n.kind() == "if_statement" && n.child_by_field_name("condition").is_some_and(|condition| ctx.text(condition).contains('%'))
+    });
+    (!guarded).then_some(found)
+}
+
+/// Whether `expr` is known to be something other than a tensor.
+fn is_non_tensor(ctx: &RuleContext, expr: Node) -> bool {
+    ctx.dataflow(expr).value_of(expr).is_some_and(|value| !matches!(value, Value::Tensor { .. }))
+}
+
+/// Whether `expr` is a tensor known to live in host memory: derived, without
+/// a move to the device, from one created or drawn from a DataLoader on the
+/// host. Model outputs are not, since where an unmoved model lives is unknown.
+fn is_on_host(ctx: &RuleContext, expr: Node) -> bool {
+    let value = |node: Node| ctx.dataflow(node).value_of(node);
+    let mut base = expr;
+    loop {
+        let inner = match base.kind() {
+            "call" => base.child_by_field_name("function").filter(|f| f.kind() == "attribute").and_then(|f| f.child_by_field_name("object")),
+            "subscript" => base.child_by_field_name("value"),
+            "parenthesized_expression" => base.named_child(0),
+            _ => None,
+        };
+        match inner {
+            Some(inner) => base = inner,
+            None => break,
+        }
+    }
+    matches!(value(expr), Some(Value::Tensor { on_device: false, .. }))
+        && matches!(value(base), Some(Value::Tensor { models, on_device: false }) if models.is_empty())
+}
+
+fn is_tensor(ctx: &RuleContext, expr: Node) -> bool {
+    matches!(ctx.dataflow(expr).value_of(expr), Some(Value::Tensor { .. }))
+}
+
+/// TG019: `.item()`, `.cpu()`, `.numpy()` and `.tolist()` copy to the host.
+pub struct TensorToHost;
+
+impl Rule for TensorToHost {
+    fn id(&self) -> &'static str {
+        "TG019"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Tensor copied to the host on every training step"
+    }
+
+    fn category(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            (#match? @method "^(item|cpu|numpy|tolist)$")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let object = captures.get("object")?;
+        // `x.cpu().numpy()` is one transfer; report the outer call only.
+        let chained = target
+            .parent()
+            .filter(|parent| parent.kind() == "attribute")
+            .and_then(|parent| parent.child_by_field_name("attribute"))
+            .is_some_and(|method| matches!(ctx.text(method), "item" | "cpu" | "numpy" | "tolist"));
+        if chained || is_non_tensor(ctx, object) || is_on_host(ctx, object) {
+            return None;
+        }
+        per_iteration(ctx, target)?;
+        Some(self.report(
+            ctx,
+            target,
+            &format!("{} waits for the GPU on every training step; {}", ctx.text(target), ADVICE),
+            None,
+        ))
+    }
+}
+
+/// TG020: printing a tensor or converting it to a Python number reads it back.
+pub struct TensorInPython;
+
+impl Rule for TensorInPython {
+    fn id(&self) -> &'static str {
+        "TG020"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Tensor printed or converted to a Python number on every training step"
+    }
+
+    fn category(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (identifier) @name
+            arguments: (argument_list) @arguments
+            (#match? @name "^(print|float|int|bool)$")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let mut cursor = target.walk();
+        let tensor = captures.get("arguments")?.named_children(&mut cursor).find_map(|arg| {
+            if is_tensor(ctx, arg) {
+                return Some(arg);
+            }
+            // Interpolating a tensor in an f-string formats its value.
+            let mut cursor = arg.walk();
+            let interpolations: Vec<Node> = arg.named_children(&mut cursor).filter(|n| n.kind() == "interpolation").collect();
+            interpolations
+                .into_iter()
+                .filter_map(|interpolation| interpolation.named_child(0))
+                .find(|expr| is_tensor(ctx, *expr))
+        })?;
+        per_iteration(ctx, target)?;
+        Some(self.report(
+            ctx,
+            target,
+            &format!(
+                "{}() reads {} back from the GPU on every training step; {}",
+                ctx.text(captures.get("name")?),
+                ctx.text(tensor),
+                ADVICE
+            ),
+            None,
+        ))
+    }
+}
+
+/// TG021: an explicit `torch.cuda.synchronize()` stalls the pipeline.
+pub struct CudaSynchronize;
+
+impl Rule for CudaSynchronize {
+    fn id(&self) -> &'static str {
+        "TG021"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`torch.cuda.synchronize()` on every training step"
+    }
+
+    fn category(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            (#eq? @object "torch.cuda")
+            (#eq? @method "synchronize")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        per_iteration(ctx, target)?;
+        Some(self.report(
+            ctx,
+            target,
+            "torch.cuda.synchronize() stalls the CPU until the GPU is idle on every training step; only synchronize around timing code",
+            None,
+        ))
+    }
+}
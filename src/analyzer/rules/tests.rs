@@ -0,0 +1,378 @@
+use super::*;
+use crate::analyzer::{CodeAnalyzer, Settings, TorchVersion};
+use anyhow::Result;
+
+fn run(rule: Box<dyn Rule>, code: &str) -> Result<Vec<Finding>> {
+    CodeAnalyzer::with_rules(vec![rule])?.analyze(code)
+}
+
+#[test]
+fn test_registry_ids_are_unique() {
+    let rules = registry();
+    let mut ids: Vec<_> = rules.iter().map(|rule| rule.id()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), rules.len());
+}
+
+#[test]
+fn test_registry_queries_compile() -> Result<()> {
+    for rule in registry() {
+        Query::new(tree_sitter_python::language(), rule.query())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_raw_cuda_once_per_call() -> Result<()> {
+    let findings = run(Box::new(gpu::RawCuda), "x = model.cuda()\ny = x.to(device)\n")?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 1);
+    assert_eq!(findings[0].category, "GPU Usage");
+    Ok(())
+}
+
+#[test]
+fn test_missing_zero_grad() -> Result<()> {
+    let rule = || Box::new(training::MissingZeroGrad);
+    assert_eq!(run(rule(), "loss.backward()\n")?.len(), 1);
+    assert!(run(rule(), "optimizer.zero_grad()\nloss.backward()\n")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_batch_size_power_of_two() -> Result<()> {
+    let rule = || Box::new(performance::BatchSizePowerOfTwo);
+    assert_eq!(run(rule(), "loader = DataLoader(ds, batch_size=30)\n")?.len(), 1);
+    assert_eq!(run(rule(), "batch_size = 100\n")?.len(), 1);
+    assert!(run(rule(), "loader = DataLoader(ds, batch_size=32)\n")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_dataloader_workers() -> Result<()> {
+    let rule = || Box::new(performance::DataLoaderWorkers);
+    assert_eq!(run(rule(), "loader = torch.utils.data.DataLoader(ds)\n")?.len(), 1);
+    assert!(run(rule(), "loader = DataLoader(ds, num_workers=4)\n")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_lr_scheduler_on_optimizer_step() -> Result<()> {
+    let rule = || Box::new(training::LrScheduler);
+    assert_eq!(run(rule(), "optimizer.step()\n")?.len(), 1);
+    assert!(run(rule(), "optimizer.step()\nscheduler.step()\n")?.is_empty());
+    assert!(run(rule(), "model.step()\n")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_lr_scheduler_needs_an_optimizer() -> Result<()> {
+    let code = r#"
+class Stepper(nn.Module):
+    pass
+
+optim_wrapper = Stepper()
+optim_wrapper.step()
+opt = torch.optim.SGD(model.parameters(), lr=0.1)
+opt.step()
+"#;
+    let findings = run(Box::new(training::LrScheduler), code)?;
+    assert_eq!(findings.iter().map(|f| f.line).collect::<Vec<_>>(), vec![8]);
+    Ok(())
+}
+
+#[test]
+fn test_step_without_backward() -> Result<()> {
+    let rule = || Box::new(training::StepWithoutBackward);
+    let missing = r#"
+def train(model, loader, optimizer):
+    for batch in loader:
+        loss = model(batch)
+        optimizer.step()
+"#;
+    let helper = r#"
+def update(loss):
+    loss.backward()
+
+def train(model, loader, optimizer):
+    for batch in loader:
+        update(model(batch))
+        optimizer.step()
+"#;
+    assert_eq!(run(rule(), missing)?.len(), 1);
+    assert!(run(rule(), helper)?.is_empty());
+    assert!(run(rule(), "loss.backward()
+optimizer.step()
+")?.is_empty());
+    assert!(run(rule(), "optimizer.step(closure)
+")?.is_empty());
+    assert!(run(rule(), "env.step()
+")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_redundant_transfer() -> Result<()> {
+    let rule = || Box::new(gpu::RedundantTransfer);
+    let code = r#"
+class Net(nn.Module):
+    pass
+
+def train(loader, device):
+    model = Net().to(device)
+    model.to(device)
+    for x in loader:
+        x = x.to(device)
+        y = x.to(device, non_blocking=True)
+        z = x.cpu().to(device)
+        w = x.to(torch.float16)
+"#;
+    let findings = run(rule(), code)?;
+    assert_eq!(findings.iter().map(|f| f.line).collect::<Vec<_>>(), vec![7, 10]);
+    Ok(())
+}
+
+#[test]
+fn test_zero_grad_in_other_function_does_not_hide_finding() -> Result<()> {
+    let code = r#"
+def reset(optimizer):
+    optimizer.zero_grad()
+
+def train(model, loader, optimizer):
+    for batch in loader:
+        loss = model(batch)
+        loss.backward()
+        optimizer.step()
+"#;
+    let findings = run(Box::new(training::MissingZeroGrad), code)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 8);
+    Ok(())
+}
+
+#[test]
+fn test_zero_grad_checked_in_enclosing_loop() -> Result<()> {
+    let outside_loop = r#"
+def train(model, loader, optimizer):
+    optimizer.zero_grad()
+    for batch in loader:
+        model(batch).backward()
+        optimizer.step()
+"#;
+    let inside_loop = r#"
+def train(model, loader, optimizer):
+    for batch in loader:
+        optimizer.zero_grad()
+        model(batch).backward()
+        optimizer.step()
+"#;
+    assert_eq!(run(Box::new(training::MissingZeroGrad), outside_loop)?.len(), 1);
+    assert!(run(Box::new(training::MissingZeroGrad), inside_loop)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_no_grad_scoped_to_function() -> Result<()> {
+    let code = r#"
+def evaluate(model, data):
+    return model(data)
+
+def validate(model, data):
+    with torch.no_grad():
+        return model(data)
+"#;
+    let findings = run(Box::new(memory::MissingNoGrad), code)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 3);
+    Ok(())
+}
+
+#[test]
+fn test_dataloader_workers_checked_per_call() -> Result<()> {
+    let code = r#"
+train_loader = DataLoader(train_set, num_workers=4)
+val_loader = DataLoader(val_set)
+"#;
+    let findings = run(Box::new(performance::DataLoaderWorkers), code)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 3);
+    Ok(())
+}
+
+#[test]
+fn test_zero_grad_after_backward() -> Result<()> {
+    let rule = || Box::new(training::ZeroGradAfterBackward);
+    let wrong = r#"
+for x, y in loader:
+    loss = criterion(model(x), y)
+    loss.backward()
+    optimizer.zero_grad()
+    optimizer.step()
+"#;
+    // Clearing after the step, ready for the next batch, is fine.
+    let after_step = r#"
+for x, y in loader:
+    loss = criterion(model(x), y)
+    loss.backward()
+    optimizer.step()
+    optimizer.zero_grad()
+"#;
+    let findings = run(rule(), wrong)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 5);
+    assert!(run(rule(), after_step)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_scheduler_before_optimizer() -> Result<()> {
+    let rule = || Box::new(training::SchedulerBeforeOptimizer);
+    let wrong = r#"
+for epoch in range(10):
+    scheduler.step()
+    for x, y in loader:
+        optimizer.zero_grad()
+        criterion(model(x), y).backward()
+        optimizer.step()
+"#;
+    let right = r#"
+for epoch in range(10):
+    for x, y in loader:
+        optimizer.zero_grad()
+        criterion(model(x), y).backward()
+        optimizer.step()
+    scheduler.step()
+"#;
+    let findings = run(rule(), wrong)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 3);
+    assert!(run(rule(), right)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_missing_train_mode() -> Result<()> {
+    let rule = || Box::new(training::MissingTrainMode);
+    let code = r#"
+model.train()
+for epoch in range(10):
+    for x, y in train_loader:
+        optimizer.zero_grad()
+        criterion(model(x), y).backward()
+        optimizer.step()
+    model.eval()
+    for x, y in val_loader:
+        model(x)
+"#;
+    let findings = run(rule(), code)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 8);
+    let fixed = code.replace("for epoch in range(10):\n", "for epoch in range(10):\n    model.train()\n");
+    assert!(run(rule(), &fixed)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_graph_accumulation_suggests_detach_only() -> Result<()> {
+    let code = r#"
+for x, y in loader:
+    optimizer.zero_grad()
+    loss = criterion(model(x), y)
+    loss.backward()
+    optimizer.step()
+    total += loss
+"#;
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    let accumulated: Vec<_> = findings.iter().filter(|f| f.rule_id == "TG022").collect();
+    assert_eq!(accumulated.len(), 1);
+    assert!(accumulated[0].message.contains("loss.detach()"));
+    assert!(!accumulated[0].message.contains(".item()"));
+
+    // Following the suggestion must not trade TG022 for a host sync (TG019).
+    let followed = CodeAnalyzer::new()?.analyze(&code.replace("total += loss", "total += loss.detach()"))?;
+    assert!(!followed.iter().any(|f| matches!(f.rule_id.as_str(), "TG019" | "TG022")));
+    Ok(())
+}
+
+fn custom(toml: &str) -> Result<CodeAnalyzer> {
+    let definition: custom::RuleDefinition = toml::from_str(toml)?;
+    let mut analyzer = CodeAnalyzer::with_rules(Vec::new())?;
+    analyzer.add_custom_rules(vec![definition])?;
+    Ok(analyzer)
+}
+
+#[test]
+fn test_custom_rule_conditions_and_templates() -> Result<()> {
+    let mut analyzer = custom(
+        r#"
+id = "ACME001"
+message = "Save {target}.state_dict() instead"
+severity = "error"
+query = """
+(call
+    function: (attribute object: (identifier) @module attribute: (identifier) @method)
+    arguments: (argument_list . (identifier) @target))
+"""
+replacement = "{target}.state_dict()"
+where = [
+    { capture = "module", equals = "torch" },
+    { capture = "method", matches = "^save$" },
+    { capture = "target", not-matches = "state" },
+]
+"#,
+    )?;
+    let code = "torch.save(model, path)\ntorch.load(model)\nnp.save(model, path)\ntorch.save(state, path)\n";
+    let findings = analyzer.analyze(code)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule_id, "ACME001");
+    assert_eq!(findings[0].category, custom::CATEGORY);
+    assert_eq!(findings[0].severity, Severity::Error);
+    assert_eq!(findings[0].message, "Save model.state_dict() instead");
+    assert!(analyzer.fix(code, false)?.starts_with("torch.save(model, path)"));
+    assert!(analyzer.fix(code, true)?.starts_with("torch.save(model.state_dict(), path)\n"));
+    Ok(())
+}
+
+#[test]
+fn test_custom_rule_errors() {
+    let error = |toml: &str| format!("{:#}", custom(toml).err().expect("rule rejected"));
+    assert!(error(r#"id = "ACME001"
+query = "(call"
+message = "m""#)
+    .starts_with("custom rule ACME001: invalid query"));
+    assert!(error(r#"id = "ACME001"
+query = "(call) @call"
+message = "m""#)
+    .contains("no @target capture"));
+    assert!(error(r#"id = "ACME001"
+query = "(call) @target"
+message = "{missing}""#)
+    .contains("unknown capture {missing}"));
+    assert!(error(r#"id = "ACME 1"
+query = "(call) @target"
+message = "m""#)
+    .contains("invalid rule id"));
+
+    let taken: custom::RuleDefinition = toml::from_str("id = \"TG001\"\nquery = \"(call) @target\"\nmessage = \"m\"").unwrap();
+    let error = CodeAnalyzer::new().unwrap().add_custom_rules(vec![taken]).unwrap_err();
+    assert!(error.to_string().contains("already taken"));
+}
+
+#[test]
+fn test_torch_load_depends_on_torch_version() -> Result<()> {
+    let code = "model.load_state_dict(torch.load(path))\nstate = torch.load(path, weights_only=False)\n";
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(security::UnsafeTorchLoad)])?;
+    assert_eq!(analyzer.analyze(code)?.len(), 2);
+
+    // From 2.6 on, weights_only defaults to True and only the explicit opt-out is reported.
+    analyzer.set_settings(Settings {
+        torch_version: Some(TorchVersion::new(2, 6)),
+        ..Default::default()
+    });
+    let findings = analyzer.analyze(code)?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, 2);
+    assert!(findings[0].fix.is_none(), "`state` is not known to be a state dict");
+    Ok(())
+}
@@ -0,0 +1,331 @@
+use super::{insert_line_before, optimizer_step, unsafe_fix, Captures, Rule, RuleContext, BACKWARD_CALL};
+use crate::analyzer::{scope, Finding, LoopKind, Severity, StepKind, TrainingLoop};
+
+/// TG003: gradients accumulate across steps unless they are cleared.
+pub struct MissingZeroGrad;
+
+impl Rule for MissingZeroGrad {
+    fn id(&self) -> &'static str {
+        "TG003"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`backward()` without `zero_grad()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Training"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        BACKWARD_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let loop_scope = scope::loop_scope(target);
+        if scope::calls(loop_scope, ctx.code, "zero_grad") || ctx.reaches(loop_scope, |id| id == "zero_grad") {
+            return None;
+        }
+        let optimizer = optimizer_step(ctx, loop_scope)
+            .and_then(|step| step.child_by_field_name("function")?.child_by_field_name("object"))
+            .map_or("optimizer", |object| ctx.text(object));
+        Some(self.report(
+            ctx,
+            target,
+            "Consider calling optimizer.zero_grad() before backward pass",
+            Some(unsafe_fix(
+                "Add optimizer.zero_grad()",
+                vec![insert_line_before(ctx, target, &format!("{}.zero_grad()", optimizer))],
+            )),
+        ))
+    }
+}
+
+/// TG008: exploding gradients are the most common cause of diverging runs.
+pub struct GradientClipping;
+
+impl Rule for GradientClipping {
+    fn id(&self) -> &'static str {
+        "TG008"
+    }
+
+    fn summary(&self) -> &'static str {
+        "No gradient clipping"
+    }
+
+    fn category(&self) -> &'static str {
+        "Training Stability"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        BACKWARD_CALL
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let function = scope::function_scope(target);
+        let is_clipping = |id: &str| id.starts_with("clip_grad");
+        if scope::mentions(function, ctx.code, is_clipping) || ctx.reaches(function, is_clipping) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "Consider using gradient clipping for training stability",
+            None,
+        ))
+    }
+}
+
+/// TG009: a fixed learning rate usually converges slower than a scheduled one.
+pub struct LrScheduler;
+
+impl Rule for LrScheduler {
+    fn id(&self) -> &'static str {
+        "TG009"
+    }
+
+    fn summary(&self) -> &'static str {
+        "No learning rate scheduler"
+    }
+
+    fn category(&self) -> &'static str {
+        "Training"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            (#eq? @method "step")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let object = captures.get("object")?;
+        if !ctx.dataflow(target).is_optimizer(object) {
+            return None;
+        }
+        let function = scope::function_scope(target);
+        let is_scheduler = |id: &str| id.contains("scheduler");
+        if scope::mentions(function, ctx.code, is_scheduler) || ctx.reaches(function, is_scheduler) {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            "Consider using a learning rate scheduler for better convergence",
+            None,
+        ))
+    }
+}
+
+/// TG010: without a backward pass there are no gradients, so `step()` does nothing.
+pub struct StepWithoutBackward;
+
+impl Rule for StepWithoutBackward {
+    fn id(&self) -> &'static str {
+        "TG010"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`optimizer.step()` without `backward()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Training"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        r#"
+        (call
+            function: (attribute
+                object: (_) @object
+                attribute: (identifier) @method)
+            arguments: (argument_list) @arguments
+            (#eq? @method "step")) @target
+        "#
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let object = captures.get("object")?;
+        // `step(closure)` (e.g. LBFGS) runs the backward pass itself.
+        if captures.get("arguments")?.named_child_count() > 0 || !ctx.dataflow(target).is_optimizer(object) {
+            return None;
+        }
+        let loop_scope = scope::loop_scope(target);
+        if scope::calls(loop_scope, ctx.code, "backward") || ctx.reaches(loop_scope, |id| id == "backward") {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            target,
+            &format!("{}.step() is called without a backward pass, so there are no gradients to apply", ctx.text(object)),
+            None,
+        ))
+    }
+}
+
+/// Matches every `for` loop; the rules below keep those over a DataLoader.
+const FOR_LOOP: &str = "(for_statement) @target";
+
+/// The training loop headed by the matched `for`, if it is one.
+fn training_loop<'tree>(ctx: &RuleContext, captures: &Captures<'_, 'tree>) -> Option<TrainingLoop<'tree>> {
+    TrainingLoop::recognize(ctx, captures.target()?).filter(|found| found.kind == LoopKind::Training)
+}
+
+/// TG012: `zero_grad()` between `backward()` and `step()` throws the gradients away.
+pub struct ZeroGradAfterBackward;
+
+impl Rule for ZeroGradAfterBackward {
+    fn id(&self) -> &'static str {
+        "TG012"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`zero_grad()` between `backward()` and `step()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Training"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn query(&self) -> &str {
+        FOR_LOOP
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let found = training_loop(ctx, captures)?;
+        // Whether gradients have been computed and not yet applied.
+        let mut pending = false;
+        let zero_grad = found.steps.iter().filter(|step| step.per_batch).find_map(|step| {
+            match step.kind {
+                StepKind::Backward => pending = true,
+                StepKind::OptimizerStep => pending = false,
+                StepKind::ZeroGrad if pending => return Some(step.node),
+                _ => {}
+            }
+            None
+        })?;
+        Some(self.report(
+            ctx,
+            zero_grad,
+            "zero_grad() runs after backward(), clearing the gradients before the optimizer step uses them",
+            None,
+        ))
+    }
+}
+
+/// TG013: stepping the scheduler first skips the initial learning rate.
+pub struct SchedulerBeforeOptimizer;
+
+impl Rule for SchedulerBeforeOptimizer {
+    fn id(&self) -> &'static str {
+        "TG013"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`scheduler.step()` before `optimizer.step()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Training"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        FOR_LOOP
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let found = training_loop(ctx, captures)?;
+        let optimizer = found.first(StepKind::OptimizerStep)?;
+        let scheduler = found.first(StepKind::SchedulerStep)?;
+        if scheduler.node.start_byte() > optimizer.node.start_byte() {
+            return None;
+        }
+        Some(self.report(
+            ctx,
+            scheduler.node,
+            "scheduler.step() runs before optimizer.step(), so the first value of the learning rate schedule is skipped",
+            None,
+        ))
+    }
+}
+
+/// TG014: after `model.eval()`, the next epoch trains with dropout and batch
+/// norm still in evaluation mode.
+pub struct MissingTrainMode;
+
+impl Rule for MissingTrainMode {
+    fn id(&self) -> &'static str {
+        "TG014"
+    }
+
+    fn summary(&self) -> &'static str {
+        "`model.eval()` in the epoch loop without `model.train()`"
+    }
+
+    fn category(&self) -> &'static str {
+        "Model State"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn query(&self) -> &str {
+        FOR_LOOP
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let found = training_loop(ctx, captures)?;
+        found.epoch_loop?;
+        let eval = found.first(StepKind::Eval)?;
+        if found.first(StepKind::Train).is_some() {
+            return None;
+        }
+        let model = eval
+            .node
+            .child_by_field_name("function")
+            .and_then(|function| function.child_by_field_name("object"))
+            .map_or("model", |object| ctx.text(object));
+        Some(self.report(
+            ctx,
+            eval.node,
+            &format!("{}.eval() is never undone, so later epochs train in evaluation mode; call {}.train() before the training loop", model, model),
+            Some(unsafe_fix(
+                &format!("Add {}.train()", model),
+                vec![insert_line_before(ctx, found.batch_loop, &format!("{}.train()", model))],
+            )),
+        ))
+    }
+}
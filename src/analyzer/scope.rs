@@ -0,0 +1,93 @@
+//! Helpers for locating the function or loop a node belongs to, so rules can
+//! look for related calls in the code that actually runs alongside it.
+
+use tree_sitter::Node;
+
+const FUNCTION_KINDS: &[&str] = &["function_definition", "lambda"];
+const LOOP_KINDS: &[&str] = &["for_statement", "while_statement"];
+
+/// The innermost function containing `node`.
+pub fn enclosing_function(node: Node) -> Option<Node> {
+    ancestors(node).find(|n| FUNCTION_KINDS.contains(&n.kind()))
+}
+
+/// The innermost class containing `node`.
+pub fn enclosing_class(node: Node) -> Option<Node> {
+    ancestors(node).find(|n| n.kind() == "class_definition")
+}
+
+/// The innermost loop containing `node`, without leaving its function.
+pub fn enclosing_loop(node: Node) -> Option<Node> {
+    ancestors(node)
+        .take_while(|n| !FUNCTION_KINDS.contains(&n.kind()))
+        .find(|n| LOOP_KINDS.contains(&n.kind()))
+}
+
+/// The enclosing function, or the module when `node` is top-level code.
+pub fn function_scope(node: Node) -> Node {
+    enclosing_function(node).unwrap_or_else(|| root(node))
+}
+
+/// The enclosing loop if there is one, otherwise the function scope.
+pub fn loop_scope(node: Node) -> Node {
+    enclosing_loop(node).unwrap_or_else(|| function_scope(node))
+}
+
+/// Whether `scope` calls `name`, either as a plain function or as a method.
+/// Nested function and class bodies are not searched.
+pub fn calls(scope: Node, code: &str, name: &str) -> bool {
+    descendants(scope).any(|n| {
+        if n.kind() != "call" {
+            return false;
+        }
+        let Some(function) = n.child_by_field_name("function") else { return false };
+        let callee = match function.kind() {
+            "attribute" => function.child_by_field_name("attribute"),
+            "identifier" => Some(function),
+            _ => None,
+        };
+        callee.is_some_and(|c| text(c, code) == name)
+    })
+}
+
+/// Whether an identifier in `scope` satisfies `pred`. Nested function and
+/// class bodies are not searched.
+pub fn mentions(scope: Node, code: &str, pred: impl Fn(&str) -> bool) -> bool {
+    descendants(scope).any(|n| n.kind() == "identifier" && pred(text(n, code)))
+}
+
+pub fn ancestors(node: Node) -> impl Iterator<Item = Node> {
+    std::iter::successors(node.parent(), |n| n.parent())
+}
+
+/// Every node below `scope`, depth first, skipping nested definitions.
+pub fn descendants(scope: Node) -> impl Iterator<Item = Node> {
+    walk(scope, true)
+}
+
+/// Every node below `node`, depth first, including nested definitions.
+pub fn all_descendants(node: Node) -> impl Iterator<Item = Node> {
+    walk(node, false)
+}
+
+fn walk(scope: Node, skip_definitions: bool) -> impl Iterator<Item = Node> {
+    let mut stack = vec![scope];
+    std::iter::from_fn(move || {
+        let node = stack.pop()?;
+        let mut cursor = node.walk();
+        let children: Vec<_> = node
+            .children(&mut cursor)
+            .filter(|c| !(skip_definitions && matches!(c.kind(), "function_definition" | "class_definition")))
+            .collect();
+        stack.extend(children.into_iter().rev());
+        Some(node)
+    })
+}
+
+fn root(node: Node) -> Node {
+    ancestors(node).last().unwrap_or(node)
+}
+
+fn text<'a>(node: Node, code: &'a str) -> &'a str {
+    node.utf8_text(code.as_bytes()).unwrap_or("")
+}
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use super::Severity;
+
+/// Per-file analysis settings, usually resolved from a configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// When set, only these rules run.
+    pub enable: Option<HashSet<String>>,
+    pub disable: HashSet<String>,
+    pub severity: HashMap<String, Severity>,
+    pub torch_version: Option<TorchVersion>,
+    pub report_unused_suppressions: bool,
+    /// Ids of the custom rules declared by the configuration these settings
+    /// come from. Custom rules missing here do not run; `None` runs them all.
+    pub custom_rules: Option<HashSet<String>>,
+}
+
+impl Settings {
+    pub fn is_enabled(&self, rule_id: &str) -> bool {
+        self.enable.as_ref().is_none_or(|enable| enable.contains(rule_id)) && !self.disable.contains(rule_id)
+    }
+
+    /// Whether the custom rule `rule_id` applies under these settings.
+    pub fn declares(&self, rule_id: &str) -> bool {
+        self.custom_rules.as_ref().is_none_or(|declared| declared.contains(rule_id))
+    }
+}
+
+/// A `major.minor` PyTorch release, e.g. `2.1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TorchVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl TorchVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        TorchVersion { major, minor }
+    }
+}
+
+impl std::str::FromStr for TorchVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().split('.');
+        let mut next = || -> Result<u32> {
+            let part = parts.next().unwrap_or("0");
+            part.parse().map_err(|_| anyhow!("invalid torch version '{}', expected e.g. 2.1", s))
+        };
+        Ok(TorchVersion::new(next()?, next()?))
+    }
+}
+
+impl TryFrom<String> for TorchVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
@@ -0,0 +1,180 @@
+//! Inline suppression comments:
+//!
+//! - `# torchguard: ignore[TG005]` silences findings on its own line,
+//! - `# torchguard: ignore-next-line[TG005]` silences the following line,
+//! - `# torchguard: disable=TG003,TG005` silences rules for the whole file.
+//!
+//! Leaving out the codes silences every rule.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use tree_sitter::Tree;
+
+use super::rules::{Captures, Rule, RuleContext};
+use super::{scope, Applicability, Edit, Finding, Fix, Severity, Span};
+
+/// Rule code reported for a suppression comment that silenced nothing.
+pub const UNUSED_SUPPRESSION: &str = "TG100";
+
+/// TG100. Its findings come from [`Suppressions::unused`] after the other
+/// rules ran, not from a query; the rule describes them in rule listings.
+pub struct UnusedSuppression;
+
+impl Rule for UnusedSuppression {
+    fn id(&self) -> &'static str {
+        UNUSED_SUPPRESSION
+    }
+
+    fn summary(&self) -> &'static str {
+        "Unused suppression comment"
+    }
+
+    fn category(&self) -> &'static str {
+        "Suppression"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        "(comment) @target"
+    }
+
+    fn check(&self, _ctx: &RuleContext, _captures: &Captures) -> Option<Finding> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Line(usize),
+    File,
+}
+
+#[derive(Debug)]
+struct Suppression {
+    scope: Scope,
+    /// `None` silences every rule.
+    codes: Option<Vec<String>>,
+    span: Span,
+    used: bool,
+}
+
+impl Suppression {
+    fn matches(&self, finding: &Finding) -> bool {
+        let in_scope = match self.scope {
+            Scope::Line(line) => finding.span.start_line == line,
+            Scope::File => true,
+        };
+        in_scope && self.codes.as_ref().is_none_or(|codes| codes.contains(&finding.rule_id))
+    }
+}
+
+/// The suppression comments of one file.
+#[derive(Debug)]
+pub struct Suppressions {
+    items: Vec<Suppression>,
+}
+
+impl Suppressions {
+    pub fn parse(code: &str, tree: &Tree) -> Self {
+        let pattern = directive_pattern();
+        let items = scope::all_descendants(tree.root_node())
+            .filter(|node| node.kind() == "comment")
+            .filter_map(|node| {
+                let text = node.utf8_text(code.as_bytes()).ok()?;
+                let caps = pattern.captures(text)?;
+                let span = Span::from_node(code, node);
+                let scope = match &caps["directive"] {
+                    "ignore" => Scope::Line(span.start_line),
+                    "ignore-next-line" => Scope::Line(span.start_line + 1),
+                    _ => Scope::File,
+                };
+                let codes = caps
+                    .name("bracketed")
+                    .or_else(|| caps.name("listed"))
+                    .map(|list| {
+                        list.as_str()
+                            .split(',')
+                            .map(|code| code.trim().to_string())
+                            .filter(|code| !code.is_empty())
+                            .collect()
+                    });
+                Some(Suppression {
+                    scope,
+                    codes,
+                    span,
+                    used: false,
+                })
+            })
+            .collect();
+        Suppressions { items }
+    }
+
+    /// Drops suppressed findings and remembers which suppressions were used.
+    pub fn apply(&mut self, findings: Vec<Finding>) -> Vec<Finding> {
+        findings
+            .into_iter()
+            .filter(|finding| {
+                let mut suppressed = false;
+                for item in self.items.iter_mut().filter(|item| item.matches(finding)) {
+                    item.used = true;
+                    suppressed = true;
+                }
+                !suppressed
+            })
+            .collect()
+    }
+
+    /// A finding for every suppression that silenced nothing, with a fix
+    /// removing the comment.
+    pub fn unused(&self, code: &str) -> Vec<Finding> {
+        self.items
+            .iter()
+            .filter(|item| !item.used)
+            .map(|item| {
+                let span = item.span;
+                let line_start = code[..span.start_byte].rfind('\n').map_or(0, |i| i + 1);
+                let own_line = code[line_start..span.start_byte].trim().is_empty();
+                // A comment alone on its line goes with the line; a trailing
+                // one goes with the whitespace before it.
+                let (start, end) = if own_line {
+                    let end = code[span.end_byte..].find('\n').map_or(code.len(), |i| span.end_byte + i + 1);
+                    (line_start, end)
+                } else {
+                    (code[..span.start_byte].trim_end_matches([' ', '\t']).len(), span.end_byte)
+                };
+                Finding {
+                    rule_id: UnusedSuppression.id().to_string(),
+                    category: UnusedSuppression.category().to_string(),
+                    message: "Suppression comment does not silence any finding".to_string(),
+                    line: span.start_line as i32,
+                    span,
+                    severity: UnusedSuppression.severity(),
+                    doc_url: UnusedSuppression.doc_url(),
+                    fix: Some(Fix {
+                        description: "Remove unused suppression".to_string(),
+                        edits: vec![Edit {
+                            span: Span::from_bytes(code, start, end),
+                            replacement: String::new(),
+                        }],
+                        applicability: Applicability::Safe,
+                    }),
+                    cell: None,
+                }
+            })
+            .collect()
+    }
+}
+
+fn directive_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^#\s*torchguard:\s*(?P<directive>ignore-next-line|ignore|disable)(?:\s*\[(?P<bracketed>[^\]]*)\]|\s*=\s*(?P<listed>[\w\s,]+))?\s*$",
+        )
+        .unwrap()
+    })
+}
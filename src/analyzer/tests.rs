@@ -1,57 +1,602 @@
-#[cfg(test)]
-mod tests {
-    use super::*;
+use super::*;
+use rules::{Captures, Rule, RuleContext};
 
-    #[test]
-    fn test_analyzer_creation() -> Result<()> {
-        let analyzer = CodeAnalyzer::new()?;
-        assert!(analyzer.query_cache.is_empty());
-        Ok(())
+/// Reports every call, through two patterns that overlap on statement calls.
+struct EveryCall;
+
+impl Rule for EveryCall {
+    fn id(&self) -> &'static str {
+        "TEST"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Every call"
+    }
+
+    fn category(&self) -> &'static str {
+        "Test"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        "(call) @target (expression_statement (call) @target)"
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        Some(self.report(ctx, target, ctx.text(target), None))
     }
+}
 
-    #[test]
-    fn test_cuda_detection() -> Result<()> {
-        let mut analyzer = CodeAnalyzer::new()?;
-        let code = r#"
+#[test]
+fn test_analyzer_creation() -> Result<()> {
+    let analyzer = CodeAnalyzer::new()?;
+    assert!(analyzer.query_cache.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_cuda_detection() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let code = r#"
 def process(model, data):
     model.cuda()
     return model(data.cuda())
 "#;
-        let findings = analyzer.analyze(code)?;
-        
-        assert_eq!(findings.len(), 2, "Should detect two cuda() calls");
-        assert!(findings.iter().all(|f| f.category == "GPU Usage"));
-        Ok(())
-    }
+    let findings = analyzer.analyze(code)?;
+    
+    assert_eq!(findings.len(), 2, "Should detect two cuda() calls");
+    assert!(findings.iter().all(|f| f.category == "GPU Usage"));
+    Ok(())
+}
 
-    #[test]
-    fn test_backward_detection() -> Result<()> {
-        let mut analyzer = CodeAnalyzer::new()?;
-        let code = r#"
+#[test]
+fn test_inference_detection() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let code = r#"
 def train_step(model, data):
     loss = model(data)
     loss.backward()
+
+def predict(model, data):
+    model.eval()
+    return model(data).argmax(1)
 "#;
-        let findings = analyzer.analyze(code)?;
-        
-        assert!(findings.iter().any(|f| 
-            f.category == "Memory Usage" && 
-            f.message.contains("torch.no_grad()")
-        ));
-        Ok(())
-    }
+    let findings = analyzer.analyze(code)?;
+
+    let no_grad: Vec<_> = findings.iter().filter(|f| f.rule_id == "TG002").collect();
+    assert_eq!(no_grad.len(), 1);
+    assert_eq!(no_grad[0].line, 8);
+    assert!(no_grad[0].message.contains("torch.inference_mode()"));
+    // argmax returns indices, which carry no autograd graph.
+    assert!(!findings.iter().any(|f| f.rule_id == "TG026"));
+    Ok(())
+}
+
+#[test]
+fn test_finding_rule_metadata() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let findings = analyzer.analyze("model.cuda()\n")?;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule_id, "TG001");
+    assert_eq!(findings[0].severity, Severity::Warning);
+    assert!(findings[0].doc_url.ends_with("#tg001"));
+    Ok(())
+}
+
+#[test]
+fn test_severity_serialization() -> Result<()> {
+    assert_eq!(serde_json::to_string(&Severity::Warning)?, "\"Warning\"");
+    assert_eq!(serde_json::to_string(&Severity::Info)?, "\"Info\"");
+    assert_eq!(serde_json::from_str::<Severity>("\"Error\"")?, Severity::Error);
+    assert!(Severity::Error > Severity::Warning);
+    assert!(Severity::Info > Severity::Hint);
+    Ok(())
+}
+
+#[test]
+fn test_findings_deduplicated_per_node() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(EveryCall)])?;
+    let findings = analyzer.analyze("loss.backward()\nprint(len(x))\nf(\n    g()\n)\n")?;
+    let reported: Vec<_> = findings.iter().map(|f| (f.line, f.message.as_str())).collect();
+    assert_eq!(reported, vec![(1, "loss.backward()"), (2, "len(x)"), (3, "f(\n    g()\n)"), (4, "g()")]);
+    Ok(())
+}
+
+#[test]
+fn test_backward_reported_once_per_rule() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let findings = analyzer.analyze("loss.backward()\n")?;
+    let mut rule_ids: Vec<_> = findings.iter().map(|f| f.rule_id.as_str()).collect();
+    let total = rule_ids.len();
+    rule_ids.dedup();
+    assert_eq!(rule_ids.len(), total);
+    Ok(())
+}
+
+#[test]
+fn test_finding_span_covers_node() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let code = "import torch\nnet = model.cuda()\n";
+    let findings = analyzer.analyze(code)?;
+    assert_eq!(findings.len(), 1);
+    let span = findings[0].span;
+    assert_eq!((span.start_line, span.start_column), (2, 7));
+    assert_eq!((span.end_line, span.end_column), (2, 19));
+    assert_eq!(&code[span.start_byte..span.end_byte], "model.cuda()");
+    assert_eq!(findings[0].line, 2);
+
+    let edit = &findings[0].fix.as_ref().unwrap().edits[0];
+    assert_eq!(edit.span, span);
+    Ok(())
+}
 
-    #[test]
-    fn test_clean_code() -> Result<()> {
-        let mut analyzer = CodeAnalyzer::new()?;
-        let code = r#"
-def predict(model, data, device):
+#[test]
+fn test_span_columns_count_characters() {
+    let code = "# é\nnamé = x.cuda()";
+    let start = code.find("x.cuda").unwrap();
+    let span = Span::from_bytes(code, start, code.len());
+    assert_eq!((span.start_line, span.start_column), (2, 8));
+    assert_eq!((span.end_line, span.end_column), (2, 16));
+}
+
+fn fix_all(code: &str) -> Result<String> {
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    Ok(apply_fixes(code, findings.iter().filter_map(|f| f.fix.as_ref())))
+}
+
+#[test]
+fn test_cuda_fix_rewrites_in_place() -> Result<()> {
+    let code = r#"import torch
+
+def process(model, data):
+    model.cuda()
+    return model(data.cuda(non_blocking=True))
+"#;
+    let expected = r#"import torch
+device = torch.device('cuda' if torch.cuda.is_available() else 'cpu')
+
+def process(model, data):
     model.to(device)
-    with torch.no_grad():
-        return model(data.to(device))
+    return model(data.to(device, non_blocking=True))
+"#;
+    assert_eq!(fix_all(code)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_cuda_fix_reuses_existing_device() -> Result<()> {
+    let code = "def process(model, device):\n    return model.cuda()\n";
+    assert_eq!(fix_all(code)?, "def process(model, device):\n    return model.to(device)\n");
+    Ok(())
+}
+
+#[test]
+fn test_cuda_fix_defines_device_when_only_mentioned() -> Result<()> {
+    // `torch.device(...)` and another function's parameter do not bind `device` here.
+    let code = "import torch\ncpu = torch.device(\"cpu\")\n\ndef g(device):\n    pass\n\ndef f(model):\n    return model.cuda()\n";
+    let fixed = fix_all(code)?;
+    assert!(fixed.contains("\ndevice = torch.device("), "{}", fixed);
+    assert!(fixed.contains("return model.to(device)"));
+
+    let imported = "from config import device\n\ndef f(model):\n    return model.cuda()\n";
+    assert_eq!(fix_all(imported)?, "from config import device\n\ndef f(model):\n    return model.to(device)\n");
+    Ok(())
+}
+
+#[test]
+fn test_cuda_fix_drops_device_keyword() -> Result<()> {
+    let code = "def f(x, device):\n    return x.cuda(device=1, non_blocking=True)\n";
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    let fix = findings[0].fix.as_ref().unwrap();
+    assert_eq!(fix.applicability, Applicability::Unsafe);
+    assert_eq!(fix_all(code)?, "def f(x, device):\n    return x.to(device, non_blocking=True)\n");
+    Ok(())
+}
+
+#[test]
+fn test_cuda_fix_inserts_device_below_docstring() -> Result<()> {
+    let code = "\"\"\"Train script.\"\"\"\nimport torch\n\nmodel = Net().cuda()\n";
+    let findings = CodeAnalyzer::with_rules(vec![Box::new(rules::gpu::RawCuda)])?.analyze(code)?;
+    assert_eq!(findings[0].fix.as_ref().unwrap().applicability, Applicability::Safe);
+    assert_eq!(
+        fix_all(code)?,
+        "\"\"\"Train script.\"\"\"\nimport torch\ndevice = torch.device('cuda' if torch.cuda.is_available() else 'cpu')\n\nmodel = Net().to(device)\n"
+    );
+
+    // Without `import torch` above it, the inserted line would not run.
+    let findings = CodeAnalyzer::with_rules(vec![Box::new(rules::gpu::RawCuda)])?.analyze("\"\"\"Doc.\"\"\"\nmodel = Net().cuda()\n")?;
+    assert_eq!(findings[0].fix.as_ref().unwrap().applicability, Applicability::Unsafe);
+    Ok(())
+}
+
+#[test]
+fn test_zero_grad_fix_inserts_before_backward() -> Result<()> {
+    let code = r#"
+def train(model, loader, optim, scheduler):
+    for batch in loader:
+        loss = model(batch)
+        loss.backward()
+        torch.nn.utils.clip_grad_norm_(model.parameters(), 1.0)
+        optim.step()
+"#;
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(rules::training::MissingZeroGrad)])?;
+    let findings = analyzer.analyze(code)?;
+    let fixed = apply_fixes(code, findings.iter().filter_map(|f| f.fix.as_ref()));
+    assert!(fixed.contains("        optim.zero_grad()\n        loss.backward()\n"));
+    assert!(analyzer.analyze(&fixed)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_zero_grad_fix_on_one_line_loop() -> Result<()> {
+    let code = "for x, y in loader: loss = criterion(model(x), y); loss.backward(); optimizer.step()\n";
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(rules::training::MissingZeroGrad)])?;
+    assert_eq!(
+        analyzer.fix(code, true)?,
+        "for x, y in loader: loss = criterion(model(x), y); optimizer.zero_grad(); loss.backward(); optimizer.step()\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_inserted_lines_keep_crlf() -> Result<()> {
+    let code = "import torch\r\n\r\ndef train(model, loader, optimizer):\r\n    for x in loader:\r\n        loss = model(x)\r\n        loss.backward()\r\n        optimizer.step()\r\n        y = x.cuda()\r\n";
+    let fixed = CodeAnalyzer::new()?.fix(code, true)?;
+    assert_ne!(fixed, code);
+    assert!(!fixed.replace("\r\n", "").contains('\n'), "bare LF in:\n{}", fixed);
+    Ok(())
+}
+
+#[test]
+fn test_mixed_precision_fix() -> Result<()> {
+    let code = r#"
+def train(model, loader, optimizer):
+    for inputs, labels in loader:
+        optimizer.zero_grad()
+        outputs = model(inputs)
+        loss = criterion(outputs, labels)
+        loss.backward()
+        optimizer.step()
+"#;
+    let expected = r#"
+def train(model, loader, optimizer):
+    scaler = torch.cuda.amp.GradScaler()
+    for inputs, labels in loader:
+        optimizer.zero_grad()
+        with torch.autocast(device_type='cuda'):
+            outputs = model(inputs)
+            loss = criterion(outputs, labels)
+        scaler.scale(loss).backward()
+        scaler.step(optimizer)
+        scaler.update()
 "#;
-        let findings = analyzer.analyze(code)?;
-        assert!(findings.is_empty(), "Should not find issues in clean code");
-        Ok(())
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(rules::performance::MixedPrecision)])?;
+    let findings = analyzer.analyze(code)?;
+    let fixed = apply_fixes(code, findings.iter().filter_map(|f| f.fix.as_ref()));
+    assert_eq!(fixed, expected);
+    assert!(analyzer.analyze(&fixed)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_apply_fixes_skips_overlapping_fix() {
+    let code = "abcdef";
+    let edit = |start, end, text: &str| Edit {
+        span: Span::from_bytes(code, start, end),
+        replacement: text.to_string(),
+    };
+    let fix = |edits| Fix {
+        description: String::new(),
+        edits,
+        applicability: Applicability::Safe,
+    };
+    let fixes = [
+        fix(vec![edit(0, 3, "X")]),
+        fix(vec![edit(2, 4, "Y"), edit(5, 6, "Z")]),
+        fix(vec![edit(4, 4, "-")]),
+        fix(vec![edit(4, 4, "-")]),
+    ];
+    assert_eq!(apply_fixes(code, &fixes), "Xd-ef");
+}
+
+/// Snippets exercising every built-in fix.
+const FIXABLE: &[&str] = &[
+    "import torch\nmodel.cuda()\nx = data.cuda(0).cuda()\n",
+    "def train(model, loader, optimizer):\n    for batch in loader:\n        loss = model(batch)\n        loss.backward()\n        optimizer.step()\n",
+    "def train(model, loader, optimizer):\n    for x, y in loader:\n        out = model(x)\n        loss = criterion(out, y)\n        loss.backward()\n        optimizer.step()\n",
+    "def test(net, test_data):\n    return net.forward(test_data)\n",
+    "for x, y in loader: loss = criterion(model(x), y); loss.backward(); optimizer.step()\n",
+];
+
+#[test]
+fn test_fixes_resolve_their_findings() -> Result<()> {
+    for rule in rules::registry() {
+        let id = rule.id().to_string();
+        let mut analyzer = CodeAnalyzer::with_rules(vec![rule])?;
+        for code in FIXABLE {
+            let findings = analyzer.analyze(code)?;
+            if findings.is_empty() || findings.iter().any(|f| f.fix.is_none()) {
+                continue;
+            }
+            let fixed = analyzer.fix(code, true)?;
+            assert!(analyzer.analyze(&fixed)?.is_empty(), "{} still reported after fixing:\n{}", id, fixed);
+        }
+    }
+    Ok(())
+}
+
+/// Offers a fix that never clears its own finding.
+struct StubbornFix;
+
+impl Rule for StubbornFix {
+    fn id(&self) -> &'static str {
+        "TEST"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Stubborn fix"
+    }
+
+    fn category(&self) -> &'static str {
+        "Test"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn query(&self) -> &str {
+        "(pass_statement) @target"
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let target = captures.target()?;
+        let fix = Fix {
+            description: "Comment".to_string(),
+            edits: vec![Edit::insert(ctx.code, target.start_byte(), "# checked\n")],
+            applicability: Applicability::Safe,
+        };
+        Some(self.report(ctx, target, "pass", Some(fix)))
+    }
+}
+
+#[test]
+fn test_fix_not_repeated_when_finding_survives() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(StubbornFix)])?;
+    assert_eq!(analyzer.fix("pass\n", false)?, "# checked\npass\n");
+    Ok(())
+}
+
+#[test]
+fn test_fix_skips_unsafe_unless_requested() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let code = "import torch\nx = data.cuda(1)\ny = data.cuda()\n";
+    let safe = analyzer.fix(code, false)?;
+    assert!(safe.contains("x = data.cuda(1)\n"));
+    assert!(safe.contains("y = data.to(device)\n"));
+    assert!(!analyzer.fix(code, true)?.contains(".cuda("));
+    Ok(())
+}
+
+#[test]
+fn test_suppression_comments() -> Result<()> {
+    let code = r#"# torchguard: disable=TG003
+batch_size = 30  # torchguard: ignore[TG005]
+x = data.cuda()  # torchguard: ignore[TG005]
+# torchguard: ignore-next-line
+y = data.cuda()
+z = data.cuda()  # torchguard: ignore
+loss.backward()
+"#;
+    let mut analyzer = CodeAnalyzer::with_rules(vec![
+        Box::new(rules::gpu::RawCuda),
+        Box::new(rules::training::MissingZeroGrad),
+        Box::new(rules::performance::BatchSizePowerOfTwo),
+    ])?;
+    let findings = analyzer.analyze(code)?;
+    let reported: Vec<_> = findings.iter().map(|f| (f.rule_id.as_str(), f.line)).collect();
+    assert_eq!(reported, vec![("TG001", 3)]);
+    Ok(())
+}
+
+#[test]
+fn test_unused_suppressions_reported_on_request() -> Result<()> {
+    let code = "x = 1  # torchguard: ignore[TG001]\n# torchguard: disable=TG005\ny = data.cuda()  # torchguard: ignore\n";
+    let mut analyzer = CodeAnalyzer::new()?;
+    assert!(analyzer.analyze(code)?.is_empty());
+
+    analyzer.set_settings(Settings {
+        report_unused_suppressions: true,
+        ..Settings::default()
+    });
+    let findings = analyzer.analyze(code)?;
+    let lines: Vec<_> = findings.iter().map(|f| (f.rule_id.as_str(), f.line)).collect();
+    assert_eq!(lines, vec![(UNUSED_SUPPRESSION, 1), (UNUSED_SUPPRESSION, 2)]);
+    assert_eq!(analyzer.fix(code, false)?, "x = 1\ny = data.cuda()  # torchguard: ignore\n");
+
+    // TG100 follows the severity overrides and can be disabled like any rule.
+    analyzer.set_settings(Settings {
+        report_unused_suppressions: true,
+        severity: [(UNUSED_SUPPRESSION.to_string(), Severity::Warning)].into(),
+        ..Settings::default()
+    });
+    assert!(analyzer.analyze(code)?.iter().all(|f| f.severity == Severity::Warning));
+    analyzer.set_settings(Settings {
+        report_unused_suppressions: true,
+        disable: [UNUSED_SUPPRESSION.to_string()].into(),
+        ..Settings::default()
+    });
+    assert!(analyzer.analyze(code)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_helpers_in_same_module_are_followed() -> Result<()> {
+    let code = r#"
+class Trainer:
+    def setup(self):
+        self.sched = torch.optim.lr_scheduler.StepLR(self.optimizer, 10)
+
+    def fit(self, loader):
+        self.setup()
+        for batch in loader:
+            self.model(batch).backward()
+            self.optimizer.step()
+
+def train(model, loader, optimizer):
+    for batch in loader:
+        model(batch).backward()
+        optimizer.step()
+"#;
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(rules::training::LrScheduler)])?;
+    let lines: Vec<_> = analyzer.analyze(code)?.iter().map(|finding| finding.line).collect();
+    assert_eq!(lines, vec![15]);
+    Ok(())
+}
+
+#[test]
+fn test_project_follows_imports() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("torchguard-project-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("pkg/utils"))?;
+    std::fs::write(dir.join("pkg/__init__.py"), "")?;
+    std::fs::write(dir.join("pkg/utils/__init__.py"), "from .optim import build_optimizer\n")?;
+    let train = "from .utils import build_optimizer\n\ndef train(model, loader):\n    optimizer, sched = build_optimizer(model)\n    for batch in loader:\n        model(batch).backward()\n        optimizer.step()\n";
+    std::fs::write(dir.join("pkg/train.py"), train)?;
+    let aliased = "import pkg.utils.optim as opt\n\ndef train(model, loader):\n    optimizer, sched = opt.build_optimizer(model)\n    for batch in loader:\n        model(batch).backward()\n        optimizer.step()\n";
+    std::fs::write(dir.join("pkg/aliased.py"), aliased)?;
+    std::fs::write(
+        dir.join("pkg/utils/optim.py"),
+        "def build_optimizer(model):\n    optimizer = make(model)\n    return optimizer, attach(optimizer)\n\ndef attach(optimizer):\n    return torch.optim.lr_scheduler.StepLR(optimizer, 10)\n",
+    )?;
+
+    let mut analyzer = CodeAnalyzer::with_rules(vec![Box::new(rules::training::LrScheduler)])?;
+    assert_eq!(analyzer.analyze(train)?.len(), 1, "imports are opaque without a project");
+
+    let files: Vec<_> = ["pkg/__init__.py", "pkg/utils/__init__.py", "pkg/utils/optim.py", "pkg/train.py", "pkg/aliased.py"]
+        .iter()
+        .map(|file| dir.join(file))
+        .collect();
+    analyzer.set_project(Project::load(&files)?);
+    analyzer.set_module_path(&dir.join("pkg/train.py"));
+    assert!(analyzer.analyze(train)?.is_empty());
+    analyzer.set_module_path(&dir.join("pkg/aliased.py"));
+    assert!(analyzer.analyze(aliased)?.is_empty());
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_dataflow_tags_bindings() -> Result<()> {
+    let code = r#"
+class Net(nn.Module):
+    pass
+
+def train(loader, device):
+    model = Net().cuda()
+    opt = torch.optim.Adam(model.parameters(), lr=1e-3)
+    sched = torch.optim.lr_scheduler.StepLR(opt, 10)
+    criterion = nn.CrossEntropyLoss()
+    for x, y in loader:
+        out = model(x.to(device))
+        loss = criterion(out, y)
+        half = x.to(torch.float16)
+        detached = loss.detach()
+"#;
+    let tree = CodeAnalyzer::new()?.parser.parse(code, None).unwrap();
+    let function = scope::all_descendants(tree.root_node()).find(|n| n.kind() == "function_definition").unwrap();
+    let dataflow = Dataflow::new(function, code);
+    let end = code.len();
+    let tensor = |models: &[&str], on_device| Value::Tensor {
+        models: models.iter().map(|m| m.to_string()).collect(),
+        on_device,
+    };
+
+    assert_eq!(dataflow.lookup("model", end), Some(Value::Module { on_device: true, loss: false }));
+    assert_eq!(dataflow.lookup("opt", end), Some(Value::Optimizer { model: Some("model".to_string()) }));
+    assert_eq!(dataflow.lookup("sched", end), Some(Value::Scheduler { optimizer: Some("opt".to_string()) }));
+    assert_eq!(dataflow.lookup("x", end), Some(tensor(&[], false)));
+    assert_eq!(dataflow.lookup("out", end), Some(tensor(&["model"], true)));
+    assert_eq!(dataflow.lookup("loss", end), Some(tensor(&["model"], true)));
+    assert_eq!(dataflow.lookup("half", end), Some(tensor(&[], false)));
+    assert_eq!(dataflow.lookup("detached", end), Some(tensor(&[], true)));
+    assert!(dataflow.lookup("loss", end).is_some_and(|loss| loss.has_graph()));
+    // Before its assignment `model` is only guessed from its name.
+    assert_eq!(dataflow.lookup("model", 0), Some(Value::Module { on_device: false, loss: false }));
+    assert_eq!(dataflow.lookup("device", end), None);
+    Ok(())
+}
+
+/// Reports the kind and steps of every recognized training loop.
+struct LoopSteps;
+
+impl Rule for LoopSteps {
+    fn id(&self) -> &'static str {
+        "TEST"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Loop steps"
+    }
+
+    fn category(&self) -> &'static str {
+        "Test"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
     }
+
+    fn query(&self) -> &str {
+        "(for_statement) @target"
+    }
+
+    fn check(&self, ctx: &RuleContext, captures: &Captures) -> Option<Finding> {
+        let found = TrainingLoop::recognize(ctx, captures.target()?)?;
+        let steps: Vec<String> = found
+            .steps
+            .iter()
+            .filter(|step| step.per_batch)
+            .map(|step| format!("{:?}", step.kind))
+            .collect();
+        let message = format!("{:?}: {}", found.kind, steps.join(" "));
+        Some(self.report(ctx, found.batch_loop, &message, None))
+    }
+}
+
+#[test]
+fn test_training_loop_steps() -> Result<()> {
+    let code = r#"
+def fit(model, train_loader, val_loader, optimizer, scheduler, criterion):
+    for epoch in range(10):
+        model.train()
+        for i, (x, y) in enumerate(train_loader):
+            optimizer.zero_grad()
+            out = model(x)
+            loss = criterion(out, y)
+            loss.backward()
+            torch.nn.utils.clip_grad_norm_(model.parameters(), 1.0)
+            optimizer.step()
+        scheduler.step()
+        model.eval()
+        for x, y in val_loader:
+            preds = model(x)
+        for i in range(3):
+            pass
+"#;
+    let findings = CodeAnalyzer::with_rules(vec![Box::new(LoopSteps)])?.analyze(code)?;
+    let messages: Vec<(i32, &str)> = findings.iter().map(|f| (f.line, f.message.as_str())).collect();
+    assert_eq!(
+        messages,
+        [
+            (5, "Training: ZeroGrad Forward Loss Backward ClipGrad OptimizerStep"),
+            (14, "Evaluation: Forward"),
+        ]
+    );
+    Ok(())
 }
@@ -0,0 +1,142 @@
+//! Recognizes training and evaluation loops: a `for` over a DataLoader, and
+//! the loop around it, usually over epochs. The steps found in them
+//! (zero_grad, forward, loss, backward, clipping, optimizer and scheduler
+//! steps, train/eval mode switches) are listed in source order, so rules can
+//! reason about their ordering.
+
+use tree_sitter::Node;
+
+use super::dataflow::{Dataflow, Value};
+use super::rules::RuleContext;
+use super::scope;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    ZeroGrad,
+    Forward,
+    Loss,
+    Backward,
+    ClipGrad,
+    OptimizerStep,
+    SchedulerStep,
+    Train,
+    Eval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopKind {
+    /// Runs backward passes or optimizer steps for each batch.
+    Training,
+    Evaluation,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Step<'tree> {
+    pub kind: StepKind,
+    /// The call making the step.
+    pub node: Node<'tree>,
+    /// Whether the step runs for every batch rather than once per epoch.
+    pub per_batch: bool,
+}
+
+#[derive(Debug)]
+pub struct TrainingLoop<'tree> {
+    pub kind: LoopKind,
+    /// The loop over a DataLoader.
+    pub batch_loop: Node<'tree>,
+    /// The loop around `batch_loop`, if any.
+    pub epoch_loop: Option<Node<'tree>>,
+    /// Steps in the epoch loop, or in the batch loop when there is none.
+    pub steps: Vec<Step<'tree>>,
+}
+
+impl<'tree> TrainingLoop<'tree> {
+    /// The loop `node` heads, if it iterates over a DataLoader.
+    pub fn recognize(ctx: &RuleContext, node: Node<'tree>) -> Option<Self> {
+        if node.kind() != "for_statement" {
+            return None;
+        }
+        let dataflow = ctx.dataflow(node);
+        if !iterates_loader(ctx, &dataflow, node.child_by_field_name("right")?) {
+            return None;
+        }
+        let epoch_loop = scope::enclosing_loop(node);
+        let steps: Vec<Step> = scope::descendants(epoch_loop.unwrap_or(node))
+            .filter(|n| n.kind() == "call")
+            .filter_map(|call| {
+                let kind = classify(ctx, &dataflow, call)?;
+                let per_batch = scope::ancestors(call).any(|ancestor| ancestor == node);
+                Some(Step { kind, node: call, per_batch })
+            })
+            .collect();
+        let trains = steps
+            .iter()
+            .any(|step| step.per_batch && matches!(step.kind, StepKind::Backward | StepKind::OptimizerStep));
+        Some(TrainingLoop {
+            kind: if trains { LoopKind::Training } else { LoopKind::Evaluation },
+            batch_loop: node,
+            epoch_loop,
+            steps,
+        })
+    }
+
+    /// Steps of `kind`, in source order.
+    pub fn steps_of(&self, kind: StepKind) -> impl Iterator<Item = &Step<'tree>> + '_ {
+        self.steps.iter().filter(move |step| step.kind == kind)
+    }
+
+    pub fn first(&self, kind: StepKind) -> Option<&Step<'tree>> {
+        self.steps_of(kind).next()
+    }
+}
+
+/// Whether `iterable` is a DataLoader, possibly wrapped in `enumerate`,
+/// `tqdm` or `iter`.
+fn iterates_loader(ctx: &RuleContext, dataflow: &Dataflow, iterable: Node) -> bool {
+    if matches!(dataflow.value_of(iterable), Some(Value::DataLoader)) {
+        return true;
+    }
+    let Some(function) = iterable.child_by_field_name("function").filter(|_| iterable.kind() == "call") else {
+        return false;
+    };
+    let wrapped = iterable.child_by_field_name("arguments").and_then(|args| args.named_child(0));
+    matches!(ctx.text(function), "enumerate" | "tqdm" | "tqdm.tqdm" | "iter")
+        && wrapped.is_some_and(|inner| iterates_loader(ctx, dataflow, inner))
+}
+
+fn classify(ctx: &RuleContext, dataflow: &Dataflow, call: Node) -> Option<StepKind> {
+    let function = call.child_by_field_name("function")?;
+    let name = match function.kind() {
+        "attribute" => ctx.text(function.child_by_field_name("attribute")?),
+        _ => ctx.text(function),
+    };
+    if name.starts_with("clip_grad") {
+        return Some(StepKind::ClipGrad);
+    }
+    let object = function.child_by_field_name("object");
+    let object_value = object.and_then(|object| dataflow.value_of(object));
+    match (name, &object_value) {
+        ("zero_grad", _) => return Some(StepKind::ZeroGrad),
+        ("backward", _) => return Some(StepKind::Backward),
+        ("step", Some(Value::Optimizer { .. })) => return Some(StepKind::OptimizerStep),
+        ("step", Some(Value::Scheduler { .. })) => return Some(StepKind::SchedulerStep),
+        // `scaler.step(optimizer)`
+        ("step", _) => {
+            let first = call.child_by_field_name("arguments").and_then(|args| args.named_child(0));
+            return first.filter(|arg| dataflow.is_optimizer(*arg)).map(|_| StepKind::OptimizerStep);
+        }
+        ("train", Some(Value::Module { .. })) => return Some(StepKind::Train),
+        ("eval", Some(Value::Module { .. })) => return Some(StepKind::Eval),
+        _ => {}
+    }
+
+    // Calling a module: its forward pass, or the loss when it is a loss
+    // module or its result is named like one.
+    let Some(Value::Module { loss, .. }) = dataflow.value_of(function) else { return None };
+    let assigned_to_loss = call
+        .parent()
+        .filter(|parent| parent.kind() == "assignment")
+        .and_then(|assignment| assignment.child_by_field_name("left"))
+        .is_some_and(|left| ctx.text(left).ends_with("loss"));
+    Some(if loss || assigned_to_loss { StepKind::Loss } else { StepKind::Forward })
+}
@@ -1,13 +1,16 @@
 use axum::{
     routing::{get, post},
     Json, Router,
-    extract::State,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use tower_http::cors::CorsLayer;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use crate::{analyzer::CodeAnalyzer, search::CodeSearch};
+use crate::{analyzer::{CodeAnalyzer, Settings}, config::Config, notebook::{self, Notebook}, sarif, search::CodeSearch};
 
 pub struct AppState {
     analyzer: Mutex<CodeAnalyzer>,
@@ -17,6 +20,20 @@ pub struct AppState {
 #[derive(Debug, Deserialize)]
 pub struct AnalyzeRequest {
     code: String,
+    /// Name reported for the code in SARIF output. A `.ipynb` name makes
+    /// `code` a notebook, with findings reported per cell.
+    #[serde(default)]
+    filename: Option<String>,
+    /// Inline project configuration, in the same shape as torchguard.toml.
+    /// Overrides are matched against `filename`.
+    #[serde(default)]
+    config: Option<Config>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AnalyzeParams {
+    /// `sarif` returns a SARIF 2.1.0 log instead of the findings list
+    format: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,20 +74,80 @@ pub async fn create_api() -> Router {
 
 async fn analyze_handler(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<AnalyzeParams>,
     Json(request): Json<AnalyzeRequest>,
-) -> Json<AnalyzeResponse> {
+) -> Response {
     println!("Received code to analyze: {}\n", request.code);
-    let mut analyzer = state.analyzer.lock().await;
-    match analyzer.analyze(&request.code) {
+    // Rule files would be read from the server's disk, so only inline rules
+    // are accepted. Checked first, before anything looks at the config.
+    if request.config.as_ref().is_some_and(|config| !config.rule_files.is_empty()) {
+        return (StatusCode::BAD_REQUEST, "Invalid config: rule-files is not supported here; inline the rules").into_response();
+    }
+    let settings = match &request.config {
+        Some(config) => {
+            let custom_rules = config.rules.iter().map(|rule| rule.id.clone()).collect();
+            match config.settings_for(request.filename.as_deref().map(Path::new), custom_rules) {
+                Ok(settings) => settings,
+                Err(e) => return (StatusCode::BAD_REQUEST, format!("Invalid config: {:#}", e)).into_response(),
+            }
+        }
+        None => Settings::default(),
+    };
+    // A `.ipynb` filename means `code` is the notebook JSON.
+    let notebook = match request.filename.as_deref() {
+        Some(name) if name.ends_with(&format!(".{}", notebook::EXTENSION)) => match Notebook::parse(&request.code) {
+            Ok(notebook) => Some(notebook),
+            Err(e) => return (StatusCode::BAD_REQUEST, format!("Invalid notebook: {:#}", e)).into_response(),
+        },
+        _ => None,
+    };
+    let code = notebook.as_ref().map_or(request.code.as_str(), Notebook::source);
+
+    // Custom rules get an analyzer of their own.
+    let mut own_analyzer = match request.config.as_ref().map(|config| &config.rules) {
+        Some(rules) if !rules.is_empty() => {
+            let analyzer = CodeAnalyzer::new().and_then(|mut analyzer| {
+                analyzer.add_custom_rules(rules.clone())?;
+                Ok(analyzer)
+            });
+            match analyzer {
+                Ok(analyzer) => Some(analyzer),
+                Err(e) => return (StatusCode::BAD_REQUEST, format!("Invalid config: {:#}", e)).into_response(),
+            }
+        }
+        _ => None,
+    };
+    let mut shared_analyzer;
+    let analyzer = match own_analyzer.as_mut() {
+        Some(analyzer) => analyzer,
+        None => {
+            shared_analyzer = state.analyzer.lock().await;
+            &mut *shared_analyzer
+        }
+    };
+    analyzer.set_settings(settings);
+    let findings = match analyzer.analyze(code) {
         Ok(findings) => {
             println!("Analysis successful. Found {} issues.", findings.len());
-            Json(AnalyzeResponse { findings })
+            match &notebook {
+                Some(notebook) => findings.into_iter().map(|finding| notebook.localize(finding)).collect(),
+                None => findings,
+            }
         }
         Err(e) => {
             eprintln!("Error analyzing code: {}", e);
-            Json(AnalyzeResponse { findings: vec![] })
+            vec![]
         }
+    };
+
+    if params.format.as_deref() == Some("sarif") {
+        let file = sarif::FileFindings {
+            uri: request.filename.unwrap_or_else(|| "input.py".to_string()),
+            findings: &findings,
+        };
+        return Json(sarif::to_sarif(analyzer.rules(), &[file])).into_response();
     }
+    Json(AnalyzeResponse { findings }).into_response()
 }
 
 async fn search_handler(
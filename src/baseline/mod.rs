@@ -0,0 +1,181 @@
+//! Baselines record the findings a project already has, so `check --baseline`
+//! only reports new ones.
+//!
+//! Findings are matched on rule code, file and a fingerprint of the flagged
+//! source lines with all whitespace removed, plus the names of the functions
+//! and classes around them. Moving code around or reformatting it keeps the
+//! match; editing the flagged code, or moving it to another function, breaks it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::analyzer::Finding;
+
+pub const DEFAULT_BASELINE: &str = "torchguard-baseline.json";
+/// Version 1 fingerprints did not include the enclosing functions.
+const VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub rule_id: String,
+    /// Relative to the directory holding the baseline file, with `/` separators.
+    pub path: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    findings: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub struct Baseline {
+    root: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl Baseline {
+    /// An empty baseline to be saved in `root`.
+    pub fn new(root: &Path) -> Self {
+        Baseline {
+            root: root.to_path_buf(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("failed to read baseline {}", path.display()))?;
+        let file: BaselineFile =
+            serde_json::from_str(&text).with_context(|| format!("invalid baseline {}", path.display()))?;
+        if file.version != VERSION {
+            bail!("{}: unsupported baseline version {}", path.display(), file.version);
+        }
+        Ok(Baseline {
+            root: parent_dir(path),
+            entries: file.findings,
+        })
+    }
+
+    /// Writes the entries sorted, so regenerating a baseline gives small diffs.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut findings = self.entries.clone();
+        findings.sort_by(|a, b| (&a.path, &a.rule_id, &a.fingerprint).cmp(&(&b.path, &b.rule_id, &b.fingerprint)));
+        let file = BaselineFile {
+            version: VERSION,
+            findings,
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)? + "\n")
+            .with_context(|| format!("failed to write baseline {}", path.display()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Records `findings` of the file at `path`.
+    pub fn record(&mut self, path: &Path, code: &str, findings: &[Finding]) {
+        let path = self.relative(path);
+        self.entries.extend(findings.iter().map(|finding| entry(&path, code, finding)));
+    }
+
+    /// Drops the findings the baseline already knows about. Each entry
+    /// absorbs one finding, so a second copy of a baselined problem is new.
+    pub fn filter(&mut self, path: &Path, code: &str, findings: Vec<Finding>) -> Vec<Finding> {
+        let path = self.relative(path);
+        findings
+            .into_iter()
+            .filter(|finding| {
+                let key = entry(&path, code, finding);
+                match self.entries.iter().position(|known| *known == key) {
+                    Some(index) => {
+                        self.entries.swap_remove(index);
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect()
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        let absolute = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let root = fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        let relative = absolute.strip_prefix(&root).unwrap_or(&absolute);
+        relative.to_string_lossy().replace('\\', "/")
+    }
+}
+
+/// Hash of the lines `finding` spans, ignoring all whitespace, and of the
+/// functions and classes enclosing them, so identical lines in different
+/// functions get different fingerprints.
+pub fn fingerprint(code: &str, finding: &Finding) -> String {
+    let lines: String = code
+        .lines()
+        .skip(finding.span.start_line.saturating_sub(1))
+        .take(finding.span.end_line + 1 - finding.span.start_line)
+        .flat_map(|line| line.chars().filter(|c| !c.is_whitespace()))
+        .collect();
+    let digest = Sha256::new()
+        .chain_update(finding.rule_id.as_bytes())
+        .chain_update([0])
+        .chain_update(enclosing_scopes(code, finding.span.start_line).as_bytes())
+        .chain_update([0])
+        .chain_update(lines.as_bytes())
+        .finalize();
+    format!("{:x}", digest)[..16].to_string()
+}
+
+/// The dotted names of the `def` and `class` blocks around 1-based `line`,
+/// outermost first, e.g. `Trainer.fit`. Found by indentation, which stays
+/// stable when lines move.
+fn enclosing_scopes(code: &str, line: usize) -> String {
+    let indent = |text: &str| text.len() - text.trim_start().len();
+    let lines: Vec<&str> = code.lines().take(line).collect();
+    let Some(flagged) = lines.last() else { return String::new() };
+    let mut level = indent(flagged);
+    let mut names = Vec::new();
+    for text in lines.iter().rev().skip(1) {
+        let trimmed = text.trim_start();
+        if level == 0 {
+            break;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') || indent(text) >= level {
+            continue;
+        }
+        level = indent(text);
+        let header = trimmed.strip_prefix("async ").unwrap_or(trimmed);
+        if let Some(rest) = header.strip_prefix("def ").or_else(|| header.strip_prefix("class ")) {
+            names.push(rest.split(|c: char| !(c.is_alphanumeric() || c == '_')).next().unwrap_or(""));
+        }
+    }
+    names.reverse();
+    names.join(".")
+}
+
+fn entry(path: &str, code: &str, finding: &Finding) -> Entry {
+    Entry {
+        rule_id: finding.rule_id.clone(),
+        path: path.to_string(),
+        fingerprint: fingerprint(code, finding),
+    }
+}
+
+/// The directory holding `path`, `.` for a bare file name.
+pub fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+#[cfg(test)]
+mod tests;
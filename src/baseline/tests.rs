@@ -0,0 +1,72 @@
+use super::*;
+use crate::analyzer::CodeAnalyzer;
+use crate::test_util::scratch_dir;
+
+const LEGACY: &str = "import torch\nmodel = model.cuda()\nbatch_size = 30\n";
+
+#[test]
+fn test_fingerprint_ignores_whitespace_and_line_shifts() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let before = analyzer.analyze(LEGACY)?;
+    let shifted = "import torch\n\n\n# setup\nmodel  =  model.cuda( )\nbatch_size = 30\n";
+    let after = analyzer.analyze(shifted)?;
+    assert_eq!(before.len(), after.len());
+    for (old, new) in before.iter().zip(&after) {
+        assert_ne!(old.span.start_line, new.span.start_line);
+        assert_eq!(fingerprint(LEGACY, old), fingerprint(shifted, new));
+    }
+
+    let edited = "import torch\nmodel = net.cuda()\nbatch_size = 30\n";
+    let edited_findings = analyzer.analyze(edited)?;
+    assert_ne!(fingerprint(LEGACY, &before[0]), fingerprint(edited, &edited_findings[0]));
+    Ok(())
+}
+
+#[test]
+fn test_fingerprint_tells_functions_apart() -> Result<()> {
+    let code = "class Trainer:\n    def fit(self):\n        model = model.cuda()\n\n\ndef evaluate():\n    model = model.cuda()\n";
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    let cuda: Vec<_> = findings.iter().filter(|f| f.rule_id == "TG001").collect();
+    assert_eq!(cuda.len(), 2);
+    assert_ne!(fingerprint(code, cuda[0]), fingerprint(code, cuda[1]));
+    assert_eq!(enclosing_scopes(code, 3), "Trainer.fit");
+    assert_eq!(enclosing_scopes(code, 7), "evaluate");
+    assert_eq!(enclosing_scopes(code, 1), "");
+    Ok(())
+}
+
+#[test]
+fn test_filter_reports_only_new_findings() -> Result<()> {
+    let dir = scratch_dir("filter");
+    let file = dir.join("train.py");
+    fs::write(&file, LEGACY)?;
+    let mut analyzer = CodeAnalyzer::new()?;
+
+    let mut baseline = Baseline::new(&dir);
+    baseline.record(&file, LEGACY, &analyzer.analyze(LEGACY)?);
+    baseline.save(&dir.join(DEFAULT_BASELINE))?;
+
+    let saved = fs::read_to_string(dir.join(DEFAULT_BASELINE))?;
+    assert!(saved.contains("\"path\": \"train.py\""), "{}", saved);
+
+    let code = format!("{}data = data.cuda()\nmodel = model.cuda()\n", LEGACY);
+    let mut baseline = Baseline::load(&dir.join(DEFAULT_BASELINE))?;
+    let remaining = baseline.filter(&file, &code, analyzer.analyze(&code)?);
+    let lines: Vec<_> = remaining.iter().map(|f| (f.rule_id.as_str(), f.span.start_line)).collect();
+    // The second `model.cuda()` is new even though its text matches a baselined
+    // one; moving `model` again is also reported.
+    assert_eq!(lines, vec![("TG001", 4), ("TG001", 5), ("TG011", 5)]);
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_load_rejects_unknown_version() -> Result<()> {
+    let dir = scratch_dir("version");
+    let path = dir.join(DEFAULT_BASELINE);
+    fs::write(&path, r#"{"version": 99, "findings": []}"#)?;
+    assert!(Baseline::load(&path).is_err());
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
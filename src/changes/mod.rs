@@ -0,0 +1,125 @@
+//! The lines a change touched, read from a unified diff, so `check` can
+//! report only findings on code the author edited.
+//!
+//! Analysis still sees whole files; only the reported findings are filtered.
+//! A finding is kept when any of its lines was added or modified. For staged
+//! changes the files are read from the index, since the line numbers are.
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::analyzer::Finding;
+
+#[derive(Debug, Default)]
+pub struct ChangedLines {
+    /// Added or modified lines of each changed file, keyed by canonical path.
+    files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+    /// Root of the repository whose index the lines come from, for `staged`.
+    index: Option<PathBuf>,
+}
+
+impl ChangedLines {
+    /// Parses a unified diff whose paths are relative to `root`. Deleted
+    /// files and pure deletions contribute no lines.
+    pub fn parse(patch: &str, root: &Path) -> Self {
+        let mut changes = ChangedLines::default();
+        let mut current: Option<PathBuf> = None;
+        for line in patch.lines() {
+            if let Some(target) = line.strip_prefix("+++ ") {
+                let target = target.split('\t').next().unwrap_or(target).trim_end();
+                current = (target != "/dev/null").then(|| {
+                    let relative = target.strip_prefix("b/").unwrap_or(target);
+                    let path = canonical(&root.join(relative));
+                    changes.files.entry(path.clone()).or_default();
+                    path
+                });
+            } else if let (Some(path), Some(range)) = (&current, line.strip_prefix("@@ ").and_then(hunk_lines)) {
+                changes.files.entry(path.clone()).or_default().extend(range);
+            }
+        }
+        changes
+    }
+
+    /// Changes between the merge base of `base` and `HEAD`, and the working
+    /// tree: what a pull request against `base` would contain, plus
+    /// uncommitted edits. `dir` is any directory inside the repository.
+    pub fn since(dir: &Path, base: &str) -> Result<Self> {
+        let root = git_root(dir)?;
+        let merge_base = git(dir, &["merge-base", base, "HEAD"])?;
+        let patch = git(dir, &["diff", "--unified=0", "--no-color", "--no-ext-diff", merge_base.trim(), "--"])?;
+        Ok(Self::parse(&patch, &root))
+    }
+
+    /// Changes staged in the index, for pre-commit hooks.
+    pub fn staged(dir: &Path) -> Result<Self> {
+        let root = git_root(dir)?;
+        let patch = git(dir, &["diff", "--cached", "--unified=0", "--no-color", "--no-ext-diff", "--"])?;
+        let mut changes = Self::parse(&patch, &root);
+        changes.index = Some(root);
+        Ok(changes)
+    }
+
+    /// The staged contents of `path` when the lines come from the index, so
+    /// that findings are reported against the code being committed rather
+    /// than unstaged edits in the working tree.
+    pub fn staged_source(&self, path: &Path) -> Result<Option<String>> {
+        let Some(root) = &self.index else { return Ok(None) };
+        let path = canonical(path);
+        let relative = path
+            .strip_prefix(canonical(root))
+            .with_context(|| format!("{} is outside the repository", path.display()))?;
+        let spec = format!(":{}", relative.to_string_lossy().replace('\\', "/"));
+        git(root, &["show", &spec]).map(Some)
+    }
+
+    /// Whether the diff touches `path` at all.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(&canonical(path))
+    }
+
+    /// The findings in `path` that overlap a changed line.
+    pub fn filter(&self, path: &Path, findings: Vec<Finding>) -> Vec<Finding> {
+        let Some(ranges) = self.files.get(&canonical(path)) else { return Vec::new() };
+        findings
+            .into_iter()
+            .filter(|finding| {
+                let lines = finding.span.start_line..=finding.span.end_line;
+                ranges.iter().any(|range| range.start() <= lines.end() && lines.start() <= range.end())
+            })
+            .collect()
+    }
+}
+
+/// The new-file lines of a hunk header such as `-3,2 +4,5 @@`.
+fn hunk_lines(header: &str) -> Option<Option<RangeInclusive<usize>>> {
+    let new = header.split_whitespace().find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new.parse().ok()?, 1),
+    };
+    Some((count > 0).then(|| start..=start + count - 1))
+}
+
+fn git_root(dir: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim()))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().context("failed to run git")?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests;
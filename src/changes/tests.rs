@@ -0,0 +1,95 @@
+use super::*;
+use crate::analyzer::CodeAnalyzer;
+use crate::test_util::scratch_dir;
+
+const PATCH: &str = "\
+diff --git a/train.py b/train.py
+index 1111111..2222222 100644
+--- a/train.py
++++ b/train.py
+@@ -2 +2,2 @@ import torch
+-model = model.cuda()
++model = model.to(device)
++data = data.cuda()
+@@ -9,2 +10,0 @@ def train():
+-    pass
+-    pass
+diff --git a/old.py b/old.py
+deleted file mode 100644
+--- a/old.py
++++ /dev/null
+@@ -1 +0,0 @@
+-x = 1
+";
+
+#[test]
+fn test_parse_hunks() {
+    let root = Path::new("/repo");
+    let changes = ChangedLines::parse(PATCH, root);
+    assert!(changes.contains(&root.join("train.py")));
+    assert!(!changes.contains(&root.join("old.py")));
+    assert_eq!(changes.files[&root.join("train.py")], vec![2..=3]);
+}
+
+#[test]
+fn test_filter_keeps_findings_on_changed_lines() -> Result<()> {
+    let dir = scratch_dir("filter");
+    let code = "import torch\nmodel = model.to(device)\ndata = data.cuda()\nlabels = labels.cuda()\n";
+    fs::write(dir.join("train.py"), code)?;
+    let changes = ChangedLines::parse(PATCH, &dir);
+
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    let lines: Vec<i32> = changes.filter(&dir.join("train.py"), findings).iter().map(|f| f.line).collect();
+    assert_eq!(lines, [3]);
+    assert!(changes.filter(&dir.join("other.py"), CodeAnalyzer::new()?.analyze(code)?).is_empty());
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_staged_reads_the_index() -> Result<()> {
+    let dir = scratch_dir("staged");
+    let run = |args: &[&str]| git(&dir, args);
+    run(&["init", "-q"])?;
+    fs::write(dir.join("train.py"), "import torch\nmodel = model.cuda()\n")?;
+    run(&["add", "train.py"])?;
+    run(&["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-qm", "initial"])?;
+
+    fs::write(dir.join("train.py"), "import torch\nmodel = model.cuda()\ndata = data.cuda()\n")?;
+    assert!(!ChangedLines::staged(&dir)?.contains(&dir.join("train.py")));
+    run(&["add", "train.py"])?;
+    let staged = ChangedLines::staged(&dir)?;
+    assert_eq!(staged.files[&canonical(&dir.join("train.py"))], vec![3..=3]);
+
+    let since = ChangedLines::since(&dir, "HEAD")?;
+    assert_eq!(since.files[&canonical(&dir.join("train.py"))], vec![3..=3]);
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_staged_source_ignores_unstaged_edits() -> Result<()> {
+    let dir = scratch_dir("partial");
+    let run = |args: &[&str]| git(&dir, args);
+    run(&["init", "-q"])?;
+    fs::write(dir.join("train.py"), "import torch\nmodel = model.cuda()\n")?;
+    run(&["add", "train.py"])?;
+    run(&["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-qm", "initial"])?;
+
+    let staged_code = "import torch\nmodel = model.cuda()\ndata = data.cuda()\n";
+    fs::write(dir.join("train.py"), staged_code)?;
+    run(&["add", "train.py"])?;
+    // Unstaged lines above the staged one shift it in the working tree.
+    fs::write(dir.join("train.py"), format!("import os\nimport sys\n{}", staged_code))?;
+
+    let staged = ChangedLines::staged(&dir)?;
+    let path = dir.join("train.py");
+    let code = staged.staged_source(&path)?.expect("staged changes read the index");
+    assert_eq!(code, staged_code);
+    let lines: Vec<i32> = staged.filter(&path, CodeAnalyzer::new()?.analyze(&code)?).iter().map(|f| f.line).collect();
+    assert_eq!(lines, [3]);
+
+    assert!(ChangedLines::since(&dir, "HEAD")?.staged_source(&path)?.is_none());
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,519 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use colored::*;
+use walkdir::WalkDir;
+
+use crate::analyzer::{CodeAnalyzer, Finding, Project, Severity};
+use crate::baseline::{self, Baseline, DEFAULT_BASELINE};
+use crate::changes::ChangedLines;
+use crate::config::{Config, ConfigResolver};
+use crate::notebook::{self, Notebook};
+use crate::sarif;
+
+pub const DEFAULT_PORT: u16 = 3003;
+
+#[derive(Debug, Parser)]
+#[command(name = "torchguard", version, about = "Find performance and correctness issues in PyTorch code")]
+pub struct Cli {
+    /// Defaults to `serve` when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Analyze Python files and report findings
+    Check(CheckArgs),
+    /// Start the HTTP API server
+    Serve(ServeArgs),
+    /// Manage the baseline of accepted findings
+    Baseline(BaselineArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// Files or directories to analyze; directories are searched for .py and .ipynb files
+    #[arg(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// Exit with status 1 when a finding at or above this severity is reported
+    #[arg(long, value_name = "SEVERITY", default_value = "hint")]
+    pub fail_on: Severity,
+
+    /// Exit with status 1 when more than this many warnings are reported
+    #[arg(long, value_name = "N")]
+    pub max_warnings: Option<usize>,
+
+    /// Apply safe fixes and write the files back
+    #[arg(long)]
+    pub fix: bool,
+
+    /// Also apply fixes that may change behavior, e.g. adding autocast
+    #[arg(long = "unsafe")]
+    pub unsafe_fixes: bool,
+
+    /// Print the fixes as a unified diff instead of writing files
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Report suppression comments that silence nothing
+    #[arg(long)]
+    pub report_unused_suppressions: bool,
+
+    /// Follow calls into the other checked modules, e.g. a scheduler created in a helper
+    #[arg(long)]
+    pub project: bool,
+
+    /// Configuration file to use instead of discovering torchguard.toml or pyproject.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Only report findings not recorded in this baseline file
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+
+    /// Only report findings on lines changed since the merge base with this git revision;
+    /// `-` reads a unified diff from stdin instead
+    #[arg(long, value_name = "REV", conflicts_with_all = ["fix", "diff", "staged"])]
+    pub diff_base: Option<String>,
+
+    /// Only report findings on lines staged for commit
+    #[arg(long, conflicts_with_all = ["fix", "diff"])]
+    pub staged: bool,
+
+    /// How to print findings
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Compiler-style messages with source excerpts
+    Text,
+    /// A SARIF 2.1.0 log for code-scanning tools
+    Sarif,
+}
+
+#[derive(Debug, Args)]
+pub struct BaselineArgs {
+    #[command(subcommand)]
+    pub command: BaselineCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BaselineCommand {
+    /// Record the current findings so `check --baseline` only reports new ones
+    Create(BaselineCreateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct BaselineCreateArgs {
+    /// Files or directories to analyze; directories are searched for .py and .ipynb files
+    #[arg(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// Where to write the baseline
+    #[arg(long, short, default_value = DEFAULT_BASELINE)]
+    pub output: PathBuf,
+
+    /// Follow calls into the other checked modules, e.g. a scheduler created in a helper
+    #[arg(long)]
+    pub project: bool,
+
+    /// Configuration file to use instead of discovering torchguard.toml or pyproject.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    pub port: u16,
+}
+
+/// Runs `torchguard check`. Exits with 1 when findings reach `--fail-on` or
+/// exceed `--max-warnings` (or, with `--diff`, when there is something to
+/// fix), and with 2 when a file could not be read or written.
+pub fn check(args: &CheckArgs) -> Result<ExitCode> {
+    let mut configs = resolver(args.config.as_deref())?;
+    let files = collect_files(&args.paths, &mut configs)?;
+    let mut baseline = args.baseline.as_deref().map(Baseline::load).transpose()?;
+    let mut analyzer = analyzer_for(&files, args.project, &mut configs)?;
+    let changes = changed_lines(args)?;
+    let mut total = 0;
+    let mut baselined = 0;
+    let mut unchanged = 0;
+    let mut fixed = 0;
+    let mut failing = false;
+    let mut had_errors = false;
+    let mut reports = Vec::new();
+
+    for path in &files {
+        if changes.as_ref().is_some_and(|changes| !changes.contains(path)) {
+            continue;
+        }
+        let staged = changes.as_ref().map_or(Ok(None), |changes| changes.staged_source(path));
+        let source = staged.and_then(|staged| match staged {
+            Some(text) => SourceFile::parse(path, text),
+            None => SourceFile::read(path),
+        });
+        let mut source = match source {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {}: {:#}", "error".red().bold(), path.display(), e);
+                had_errors = true;
+                continue;
+            }
+        };
+
+        let mut settings = configs.settings_for(path)?;
+        settings.report_unused_suppressions |= args.report_unused_suppressions;
+        analyzer.set_settings(settings);
+        analyzer.set_module_path(path);
+
+        if args.diff {
+            let original = source.code().to_string();
+            if source.fix(&mut analyzer, args.unsafe_fixes)? {
+                print!("{}", unified_diff(path, &original, source.code()));
+                failing = true;
+            }
+            continue;
+        }
+
+        if args.fix {
+            let before = analyzer.analyze(source.code())?.len();
+            if source.fix(&mut analyzer, args.unsafe_fixes)? {
+                if let Err(e) = fs::write(path, source.contents()?) {
+                    eprintln!("{}: {}: {}", "error".red().bold(), path.display(), e);
+                    had_errors = true;
+                    continue;
+                }
+                fixed += before.saturating_sub(analyzer.analyze(source.code())?.len());
+            }
+        }
+
+        let mut findings = analyzer.analyze(source.code())?;
+        if let Some(baseline) = &mut baseline {
+            let count = findings.len();
+            findings = baseline.filter(path, source.code(), findings);
+            baselined += count - findings.len();
+        }
+        // Notebook lines cannot be matched against a diff of the JSON, so a
+        // changed notebook keeps all its findings.
+        if let Some(changes) = changes.as_ref().filter(|_| !matches!(source, SourceFile::Notebook(_))) {
+            let count = findings.len();
+            findings = changes.filter(path, findings);
+            unchanged += count - findings.len();
+        }
+        let findings: Vec<Finding> = findings.into_iter().map(|finding| source.localize(finding)).collect();
+        failing |= findings.iter().any(|finding| finding.severity >= args.fail_on);
+        total += findings.len();
+        if args.format == OutputFormat::Text {
+            for finding in &findings {
+                print!("{}", render(path, source.excerpt(finding), finding));
+            }
+        }
+        reports.push((path, findings));
+    }
+
+    if args.format == OutputFormat::Sarif && !args.diff {
+        let files: Vec<_> = reports
+            .iter()
+            .map(|(path, findings)| sarif::FileFindings {
+                uri: path.to_string_lossy().replace('\\', "/"),
+                findings,
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&sarif::to_sarif(analyzer.rules(), &files))?);
+    } else if !args.diff {
+        if fixed > 0 {
+            println!("{}", format!("Fixed {} issue(s)", fixed).green());
+        }
+        let summary = format!("Found {} issue(s) in {} file(s)", total, files.len());
+        if total == 0 {
+            println!("{}", summary.green());
+        } else {
+            println!("{}", summary.bold());
+        }
+        if baselined > 0 {
+            println!("{} baselined finding(s) not shown", baselined);
+        }
+        if unchanged > 0 {
+            println!("{} finding(s) outside the changed lines not shown", unchanged);
+        }
+        if total > 0 {
+            print!("\n{}", summary_table(reports.iter().flat_map(|(_, findings)| findings)));
+        }
+    }
+
+    if let Some(max) = args.max_warnings.filter(|_| !args.diff) {
+        let warnings = reports
+            .iter()
+            .flat_map(|(_, findings)| findings)
+            .filter(|finding| finding.severity == Severity::Warning)
+            .count();
+        if warnings > max {
+            eprintln!("{}: {} warning(s) exceed --max-warnings {}", "error".red().bold(), warnings, max);
+            failing = true;
+        }
+    }
+
+    Ok(if had_errors {
+        ExitCode::from(2)
+    } else if failing {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// The lines to report on, when `--diff-base` or `--staged` is given. Git
+/// runs in the directory of the first checked path.
+fn changed_lines(args: &CheckArgs) -> Result<Option<ChangedLines>> {
+    let first = args.paths.first().map_or(Path::new("."), PathBuf::as_path);
+    let dir = match first.parent() {
+        _ if first.is_dir() => first,
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match args.diff_base.as_deref() {
+        Some("-") => {
+            let mut patch = String::new();
+            io::stdin().read_to_string(&mut patch).context("failed to read the diff from stdin")?;
+            Ok(Some(ChangedLines::parse(&patch, Path::new("."))))
+        }
+        Some(base) => ChangedLines::since(dir, base).map(Some),
+        None if args.staged => ChangedLines::staged(dir).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Counts of `findings` per rule and per category, as aligned tables.
+pub fn summary_table<'a>(findings: impl IntoIterator<Item = &'a Finding>) -> String {
+    let mut rules: BTreeMap<&str, (&str, Severity, usize)> = BTreeMap::new();
+    let mut categories: BTreeMap<&str, usize> = BTreeMap::new();
+    for finding in findings {
+        let entry = rules.entry(&finding.rule_id).or_insert((&finding.category, finding.severity, 0));
+        entry.1 = entry.1.max(finding.severity);
+        entry.2 += 1;
+        *categories.entry(&finding.category).or_default() += 1;
+    }
+
+    let width = categories.keys().map(|category| category.len()).chain(["Category".len()]).max().unwrap_or(0);
+    let id_width = rules.keys().map(|id| id.len()).chain(["Rule".len()]).max().unwrap_or(0);
+    let mut out = format!("{:id_width$}  {:width$}  {:8}  {:>5}\n", "Rule", "Category", "Severity", "Count");
+    for (id, (category, severity, count)) in &rules {
+        out.push_str(&format!("{:id_width$}  {:width$}  {:8}  {:>5}\n", id, category, severity.to_string(), count));
+    }
+    out.push_str(&format!("\n{:width$}  {:>5}\n", "Category", "Count"));
+    for (category, count) in &categories {
+        out.push_str(&format!("{:width$}  {:>5}\n", category, count));
+    }
+    out
+}
+
+/// Runs `torchguard baseline create`, recording every current finding.
+pub fn create_baseline(args: &BaselineCreateArgs) -> Result<ExitCode> {
+    let mut configs = resolver(args.config.as_deref())?;
+    let files = collect_files(&args.paths, &mut configs)?;
+    let mut analyzer = analyzer_for(&files, args.project, &mut configs)?;
+    let mut baseline = Baseline::new(&baseline::parent_dir(&args.output));
+    for path in &files {
+        let source = SourceFile::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        analyzer.set_settings(configs.settings_for(path)?);
+        analyzer.set_module_path(path);
+        baseline.record(path, source.code(), &analyzer.analyze(source.code())?);
+    }
+    baseline.save(&args.output)?;
+    println!("Recorded {} finding(s) from {} file(s) in {}", baseline.len(), files.len(), args.output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// A file being checked: a Python module, or a notebook analyzed through its code cells.
+enum SourceFile {
+    Python(String),
+    Notebook(Notebook),
+}
+
+impl SourceFile {
+    fn read(path: &Path) -> Result<Self> {
+        Self::parse(path, fs::read_to_string(path)?)
+    }
+
+    /// `text` as the contents of `path`, which decides whether it is a notebook.
+    fn parse(path: &Path, text: String) -> Result<Self> {
+        if path.extension().is_some_and(|ext| ext == notebook::EXTENSION) {
+            Ok(SourceFile::Notebook(Notebook::parse(&text)?))
+        } else {
+            Ok(SourceFile::Python(text))
+        }
+    }
+
+    /// The Python source to analyze.
+    fn code(&self) -> &str {
+        match self {
+            SourceFile::Python(code) => code,
+            SourceFile::Notebook(notebook) => notebook.source(),
+        }
+    }
+
+    /// Applies fixes; returns whether anything changed.
+    fn fix(&mut self, analyzer: &mut CodeAnalyzer, include_unsafe: bool) -> Result<bool> {
+        match self {
+            SourceFile::Python(code) => {
+                let fixed = analyzer.fix(code, include_unsafe)?;
+                let changed = fixed != *code;
+                *code = fixed;
+                Ok(changed)
+            }
+            SourceFile::Notebook(notebook) => notebook.fix(analyzer, include_unsafe),
+        }
+    }
+
+    /// The file contents to write back.
+    fn contents(&self) -> Result<String> {
+        match self {
+            SourceFile::Python(code) => Ok(code.clone()),
+            SourceFile::Notebook(notebook) => notebook.to_json(),
+        }
+    }
+
+    fn localize(&self, finding: Finding) -> Finding {
+        match self {
+            SourceFile::Python(_) => finding,
+            SourceFile::Notebook(notebook) => notebook.localize(finding),
+        }
+    }
+
+    /// The code a localized finding's span points into.
+    fn excerpt(&self, finding: &Finding) -> &str {
+        match (self, finding.cell) {
+            (SourceFile::Notebook(notebook), Some(cell)) => notebook.cell_source(cell).unwrap_or(""),
+            _ => self.code(),
+        }
+    }
+}
+
+/// An analyzer running the built-in rules plus the custom rules of every
+/// configuration that applies to `files`; each custom rule only runs on the
+/// files its configuration governs. In project mode it indexes `files` up front.
+fn analyzer_for(files: &[PathBuf], project: bool, configs: &mut ConfigResolver) -> Result<CodeAnalyzer> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    for file in files {
+        configs.config_for(file)?;
+    }
+    analyzer.add_custom_rules(configs.custom_rules()?)?;
+    if project {
+        analyzer.set_project(Project::load(files)?);
+    }
+    Ok(analyzer)
+}
+
+fn resolver(config: Option<&Path>) -> Result<ConfigResolver> {
+    Ok(ConfigResolver::new(config.map(Config::load).transpose()?))
+}
+
+/// Expands `paths` into the Python files to analyze. Files named explicitly
+/// are always included; directories are walked for `.py` and `.ipynb` files, skipping
+/// hidden directories, `__pycache__` and whatever the configuration excludes.
+pub fn collect_files(paths: &[PathBuf], configs: &mut ConfigResolver) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+        } else if path.is_dir() {
+            let mut walker = WalkDir::new(path).sort_by_file_name().into_iter();
+            while let Some(entry) = walker.next() {
+                let entry = entry?;
+                if entry.file_type().is_dir() {
+                    if entry.depth() > 0 && (is_ignored_dir(entry.path()) || configs.is_excluded(entry.path())?) {
+                        walker.skip_current_dir();
+                    }
+                } else if entry.file_type().is_file()
+                    && entry.path().extension().is_some_and(|ext| ext == "py" || ext == notebook::EXTENSION)
+                    && configs.is_included(entry.path())?
+                {
+                    files.push(entry.into_path());
+                }
+            }
+        } else {
+            bail!("{}: no such file or directory", path.display());
+        }
+    }
+    Ok(files)
+}
+
+fn is_ignored_dir(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    path.is_dir() && (name.starts_with('.') || name == "__pycache__")
+}
+
+/// Formats a finding compiler-style, with the offending source line underlined.
+/// For notebook findings `code` is the source of the finding's cell.
+pub fn render(path: &Path, code: &str, finding: &Finding) -> String {
+    let span = finding.span;
+    let source_line = code.lines().nth(span.start_line - 1).unwrap_or("");
+    let width = if span.end_line == span.start_line {
+        span.end_column.saturating_sub(span.start_column).max(1)
+    } else {
+        (source_line.chars().count() + 1).saturating_sub(span.start_column).max(1)
+    };
+    let location = match finding.cell {
+        Some(cell) => format!("{}[cell {}]", path.display(), cell),
+        None => path.display().to_string(),
+    };
+    let gutter = " ".repeat(span.start_line.to_string().len());
+    let pipe = "|".bright_blue().bold();
+
+    let mut out = format!(
+        "{}: {}\n{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}\n",
+        format!("{}[{}]", finding.severity, finding.rule_id).color(severity_color(finding.severity)).bold(),
+        finding.message.bold(),
+        gutter,
+        "-->".bright_blue().bold(),
+        location,
+        span.start_line,
+        span.start_column,
+        gutter,
+        pipe,
+        span.start_line.to_string().bright_blue().bold(),
+        pipe,
+        source_line,
+        gutter,
+        pipe,
+        " ".repeat(span.start_column - 1),
+        "^".repeat(width).color(severity_color(finding.severity)).bold(),
+    );
+    if let Some(fix) = &finding.fix {
+        out.push_str(&format!("{} {} help: {}\n", gutter, "=".bright_blue().bold(), fix.description));
+    }
+    out.push('\n');
+    out
+}
+
+/// A unified diff between the original and fixed contents of `path`.
+pub fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    let name = path.display().to_string();
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&format!("a/{}", name), &format!("b/{}", name))
+        .to_string()
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Error => Color::Red,
+        Severity::Warning => Color::Yellow,
+        Severity::Info => Color::Blue,
+        Severity::Hint => Color::Cyan,
+    }
+}
+
+#[cfg(test)]
+mod tests;
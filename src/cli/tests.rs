@@ -0,0 +1,245 @@
+use super::*;
+use crate::test_util::scratch_dir;
+
+#[test]
+fn test_collect_files_walks_python_sources() -> Result<()> {
+    let dir = scratch_dir("collect");
+    fs::create_dir_all(dir.join("pkg/__pycache__"))?;
+    fs::create_dir_all(dir.join(".venv"))?;
+    fs::write(dir.join("train.py"), "")?;
+    fs::write(dir.join("README.md"), "")?;
+    fs::write(dir.join("pkg/model.py"), "")?;
+    fs::write(dir.join("pkg/__pycache__/model.py"), "")?;
+    fs::write(dir.join(".venv/torch.py"), "")?;
+
+    let files = collect_files(std::slice::from_ref(&dir), &mut ConfigResolver::default())?;
+    assert_eq!(files, vec![dir.join("pkg/model.py"), dir.join("train.py")]);
+
+    let explicit = collect_files(&[dir.join("README.md")], &mut ConfigResolver::default())?;
+    assert_eq!(explicit, vec![dir.join("README.md")]);
+
+    assert!(collect_files(&[dir.join("missing.py")], &mut ConfigResolver::default()).is_err());
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_honors_config() -> Result<()> {
+    let dir = scratch_dir("collect-config");
+    fs::create_dir_all(dir.join("build"))?;
+    fs::create_dir_all(dir.join("src"))?;
+    fs::write(dir.join("torchguard.toml"), "exclude = [\"build\", \"src/gen_*.py\"]\n")?;
+    fs::write(dir.join("build/out.py"), "")?;
+    fs::write(dir.join("src/gen_model.py"), "")?;
+    fs::write(dir.join("src/train.py"), "")?;
+
+    let files = collect_files(std::slice::from_ref(&dir), &mut ConfigResolver::default())?;
+    assert_eq!(files, vec![dir.join("src/train.py")]);
+
+    // Naming an excluded file still checks it.
+    let explicit = collect_files(&[dir.join("build/out.py")], &mut ConfigResolver::default())?;
+    assert_eq!(explicit, vec![dir.join("build/out.py")]);
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_custom_rules_scoped_to_their_config() -> Result<()> {
+    let dir = scratch_dir("custom-scope");
+    let rule = |id: &str| format!("[[rules]]\nid = \"{}\"\nquery = \"(call function: (identifier) @name (#eq? @name \\\"breakpoint\\\")) @target\"\nmessage = \"no\"\n", id);
+    for (sub, id) in [("a", "ACME001"), ("b", "ACME002")] {
+        fs::create_dir_all(dir.join(sub))?;
+        fs::write(dir.join(sub).join("torchguard.toml"), rule(id))?;
+        fs::write(dir.join(sub).join("train.py"), "breakpoint()\n")?;
+    }
+    fs::write(dir.join("loose.py"), "breakpoint()\n")?;
+
+    let files = vec![dir.join("a/train.py"), dir.join("b/train.py"), dir.join("loose.py")];
+    let mut configs = ConfigResolver::default();
+    let mut analyzer = analyzer_for(&files, false, &mut configs)?;
+    let mut rule_ids = Vec::new();
+    for path in &files {
+        analyzer.set_settings(configs.settings_for(path)?);
+        let findings = analyzer.analyze(&fs::read_to_string(path)?)?;
+        rule_ids.push(findings.into_iter().map(|f| f.rule_id).collect::<Vec<_>>());
+    }
+    assert_eq!(rule_ids, vec![vec!["ACME001"], vec!["ACME002"], vec![]]);
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_render_points_at_span() -> Result<()> {
+    colored::control::set_override(false);
+    let code = "import torch\nnet = model.cuda()\n";
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    let rendered = render(Path::new("train.py"), code, &findings[0]);
+    let expected = "\
+warning[TG001]: Consider using device-agnostic code with .to(device)
+ --> train.py:2:7
+  |
+2 | net = model.cuda()
+  |       ^^^^^^^^^^^^
+  = help: Use device-agnostic code
+
+";
+    assert_eq!(rendered, expected);
+    Ok(())
+}
+
+#[test]
+fn test_check_args_parse_severity() {
+    let cli = Cli::parse_from(["torchguard", "check", "src", "--fail-on", "warning"]);
+    match cli.command {
+        Some(Command::Check(args)) => {
+            assert_eq!(args.paths, vec![PathBuf::from("src")]);
+            assert_eq!(args.fail_on, Severity::Warning);
+        }
+        other => panic!("unexpected command: {:?}", other),
+    }
+    assert!(Cli::try_parse_from(["torchguard", "check", "--fail-on", "fatal"]).is_err());
+}
+
+#[test]
+fn test_unified_diff() {
+    let diff = unified_diff(Path::new("train.py"), "model.cuda()\n", "model.to(device)\n");
+    assert_eq!(diff, "--- a/train.py\n+++ b/train.py\n@@ -1 +1 @@\n-model.cuda()\n+model.to(device)\n");
+}
+
+#[test]
+fn test_check_fix_writes_files() -> Result<()> {
+    let dir = scratch_dir("fix");
+    let file = dir.join("train.py");
+    let code = "import torch\nmodel = model.cuda(0)\ndata = data.cuda()\n";
+    fs::write(&file, code)?;
+
+    let args = |extra: &[&str]| {
+        let mut argv = vec!["torchguard", "check", file.to_str().unwrap()];
+        argv.extend_from_slice(extra);
+        match Cli::parse_from(argv).command {
+            Some(Command::Check(args)) => args,
+            other => panic!("unexpected command: {:?}", other),
+        }
+    };
+
+    check(&args(&["--diff"]))?;
+    assert_eq!(fs::read_to_string(&file)?, code, "--diff must not write");
+
+    check(&args(&["--fix"]))?;
+    let safe = fs::read_to_string(&file)?;
+    assert!(safe.contains("data = data.to(device)\n"));
+    assert!(safe.contains("model = model.cuda(0)\n"), "unsafe fix applied without --unsafe");
+
+    check(&args(&["--fix", "--unsafe"]))?;
+    assert!(!fs::read_to_string(&file)?.contains(".cuda("));
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_check_baseline_hides_recorded_findings() -> Result<()> {
+    let dir = scratch_dir("baseline");
+    let file = dir.join("train.py");
+    let output = dir.join(DEFAULT_BASELINE);
+    fs::write(&file, "import torch\nmodel = model.cuda()\n")?;
+
+    let create = match Cli::parse_from(["torchguard", "baseline", "create", dir.to_str().unwrap(), "-o", output.to_str().unwrap()]).command {
+        Some(Command::Baseline(BaselineArgs { command: BaselineCommand::Create(args) })) => args,
+        other => panic!("unexpected command: {:?}", other),
+    };
+    create_baseline(&create)?;
+
+    let check_args = || match Cli::parse_from(["torchguard", "check", dir.to_str().unwrap(), "--baseline", output.to_str().unwrap()]).command {
+        Some(Command::Check(args)) => args,
+        other => panic!("unexpected command: {:?}", other),
+    };
+    assert_eq!(check(&check_args())?, ExitCode::SUCCESS);
+
+    fs::write(&file, "import torch\n\nmodel = model.cuda()\ndata = data.cuda()\n")?;
+    assert_eq!(check(&check_args())?, ExitCode::from(1));
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_check_fixes_notebooks() -> Result<()> {
+    let dir = scratch_dir("notebook");
+    let file = dir.join("explore.ipynb");
+    let notebook = r#"{
+ "cells": [
+  {"cell_type": "code", "execution_count": 1, "metadata": {}, "outputs": [], "source": ["%load_ext autoreload\n", "import torch\n"]},
+  {"cell_type": "code", "execution_count": 2, "metadata": {}, "outputs": [{"output_type": "stream", "name": "stdout", "text": ["hi\n"]}], "source": ["data = data.cuda()\n"]}
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"#;
+    fs::write(&file, notebook)?;
+    assert_eq!(collect_files(std::slice::from_ref(&dir), &mut ConfigResolver::default())?, vec![file.clone()]);
+
+    let source = SourceFile::read(&file)?;
+    let findings = CodeAnalyzer::new()?.analyze(source.code())?;
+    let localized = source.localize(findings.into_iter().next().unwrap());
+    colored::control::set_override(false);
+    assert!(render(&file, source.excerpt(&localized), &localized).contains("explore.ipynb[cell 2]:1:8\n"));
+
+    let args = match Cli::parse_from(["torchguard", "check", file.to_str().unwrap(), "--fix"]).command {
+        Some(Command::Check(args)) => args,
+        other => panic!("unexpected command: {:?}", other),
+    };
+    check(&args)?;
+    let fixed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&file)?)?;
+    assert_eq!(fixed["cells"][0]["source"][0], "%load_ext autoreload\n");
+    assert_eq!(fixed["cells"][1]["outputs"][0]["text"][0], "hi\n");
+    assert!(fixed["cells"][1]["source"].to_string().contains("data.to(device)"));
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_check_max_warnings() -> Result<()> {
+    let dir = scratch_dir("max-warnings");
+    let file = dir.join("train.py");
+    // Two TG001 warnings and one TG005 info.
+    fs::write(&file, "import torch\nmodel = model.cuda()\ndata = data.cuda()\nloader = DataLoader(ds, batch_size=100, num_workers=2, persistent_workers=True)\n")?;
+    let check_args = |extra: &[&str]| {
+        let mut argv = vec!["torchguard", "check", file.to_str().unwrap()];
+        argv.extend_from_slice(extra);
+        match Cli::parse_from(argv).command {
+            Some(Command::Check(args)) => args,
+            other => panic!("unexpected command: {:?}", other),
+        }
+    };
+    assert_eq!(check(&check_args(&["--fail-on", "error", "--max-warnings", "2"]))?, ExitCode::SUCCESS);
+    assert_eq!(check(&check_args(&["--fail-on", "error", "--max-warnings", "1"]))?, ExitCode::from(1));
+    assert_eq!(check(&check_args(&["--fail-on", "warning"]))?, ExitCode::from(1));
+    assert_eq!(check(&check_args(&["--fail-on", "error"]))?, ExitCode::SUCCESS);
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_summary_table() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    let findings = analyzer.analyze("model = model.cuda()\ndata = data.cuda()\nloader = DataLoader(ds, batch_size=100, num_workers=2, persistent_workers=True)\n")?;
+    let expected = "\
+Rule   Category     Severity  Count
+TG001  GPU Usage    warning       2
+TG005  Performance  info          1
+
+Category     Count
+GPU Usage        2
+Performance      1
+";
+    assert_eq!(summary_table(&findings), expected);
+    Ok(())
+}
+
+#[test]
+fn test_changed_line_modes_do_not_fix() {
+    assert!(Cli::try_parse_from(["torchguard", "check", "--diff-base", "origin/main"]).is_ok());
+    assert!(Cli::try_parse_from(["torchguard", "check", "--staged", "--fix"]).is_err());
+    assert!(Cli::try_parse_from(["torchguard", "check", "--diff-base", "-", "--diff"]).is_err());
+    assert!(Cli::try_parse_from(["torchguard", "check", "--diff-base", "-", "--staged"]).is_err());
+}
@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+use crate::analyzer::rules::custom::RuleDefinition;
+use crate::analyzer::rules::registry;
+use crate::analyzer::{Settings, Severity, TorchVersion, UNUSED_SUPPRESSION};
+
+pub const CONFIG_FILE: &str = "torchguard.toml";
+pub const PYPROJECT_FILE: &str = "pyproject.toml";
+
+/// Project configuration, read from `torchguard.toml` or the
+/// `[tool.torchguard]` table of `pyproject.toml`.
+///
+/// ```toml
+/// disable = ["TG005"]
+/// exclude = ["build/**"]
+/// torch-version = "2.1"
+///
+/// [severity]
+/// TG009 = "warning"
+///
+/// [[overrides]]
+/// paths = ["notebooks/**"]
+/// disable = ["TG001"]
+///
+/// [[rules]]
+/// id = "ACME001"
+/// query = "(call function: (identifier) @name (#eq? @name \"breakpoint\")) @target"
+/// message = "Remove breakpoint() before committing"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// When set, only these rules run.
+    pub enable: Option<Vec<String>>,
+    pub disable: Vec<String>,
+    /// Severity overrides by rule code.
+    pub severity: HashMap<String, Severity>,
+    /// Globs selecting the files to check when walking directories. Empty means every `.py` file.
+    pub include: Vec<String>,
+    /// Globs of files and directories to skip when walking directories.
+    pub exclude: Vec<String>,
+    pub torch_version: Option<TorchVersion>,
+    pub report_unused_suppressions: bool,
+    /// Settings for files matching `paths`, applied in order after the top level.
+    pub overrides: Vec<Override>,
+    /// Custom rules; see [`RuleDefinition`].
+    pub rules: Vec<RuleDefinition>,
+    /// YAML or TOML files holding more custom rules under a `rules` key.
+    pub rule_files: Vec<PathBuf>,
+    /// Directory the globs are relative to: the one holding the configuration file.
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+/// A file listed in `rule-files`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    rules: Vec<RuleDefinition>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Override {
+    pub paths: Vec<String>,
+    pub enable: Option<Vec<String>>,
+    pub disable: Vec<String>,
+    pub severity: HashMap<String, Severity>,
+}
+
+impl Config {
+    /// Reads a `torchguard.toml`, or the `[tool.torchguard]` table when `path`
+    /// is a `pyproject.toml`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut config = if path.file_name().is_some_and(|name| name == PYPROJECT_FILE) {
+            Self::from_pyproject(&text)?.unwrap_or_default()
+        } else {
+            toml::from_str(&text).with_context(|| format!("invalid configuration in {}", path.display()))?
+        };
+        config.root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Finds the configuration for `path` by walking up its ancestors. In each
+    /// directory `torchguard.toml` wins over a `pyproject.toml` that has a
+    /// `[tool.torchguard]` table.
+    pub fn discover(path: &Path) -> Result<Option<Self>> {
+        let start = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        for dir in start.ancestors().filter(|dir| dir.is_dir()) {
+            let candidate = dir.join(CONFIG_FILE);
+            if candidate.is_file() {
+                return Self::load(&candidate).map(Some);
+            }
+            let pyproject = dir.join(PYPROJECT_FILE);
+            if pyproject.is_file() {
+                let text = fs::read_to_string(&pyproject)?;
+                if let Some(mut config) = Self::from_pyproject(&text)
+                    .with_context(|| format!("invalid configuration in {}", pyproject.display()))?
+                {
+                    config.root = dir.to_path_buf();
+                    return Ok(Some(config));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn from_pyproject(text: &str) -> Result<Option<Self>> {
+        let document: toml::Table = toml::from_str(text)?;
+        match document.get("tool").and_then(|tool| tool.get("torchguard")) {
+            Some(table) => Ok(Some(table.clone().try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The settings for `path` after applying every matching override.
+    /// Without a path only the top-level settings apply. `custom_rules` are
+    /// the ids of this configuration's custom rules; rule files are not read
+    /// here, since a configuration sent over the API must not reach the disk.
+    /// Fails when a rule code names neither a built-in nor a custom rule.
+    pub fn settings_for(&self, path: Option<&Path>, custom_rules: HashSet<String>) -> Result<Settings> {
+        self.check_rule_codes(&custom_rules)?;
+        let mut settings = Settings {
+            enable: self.enable.as_ref().map(|rules| rules.iter().cloned().collect()),
+            disable: self.disable.iter().cloned().collect(),
+            severity: self.severity.clone(),
+            torch_version: self.torch_version,
+            report_unused_suppressions: self.report_unused_suppressions,
+            custom_rules: Some(custom_rules),
+        };
+        let Some(path) = path else { return Ok(settings) };
+        let relative = self.relative(path);
+        for item in &self.overrides {
+            if !glob_set(&item.paths)?.is_match(&relative) {
+                continue;
+            }
+            if let Some(enable) = &item.enable {
+                settings.enable = Some(enable.iter().cloned().collect());
+            }
+            for rule in &item.disable {
+                settings.disable.insert(rule.clone());
+            }
+            settings.severity.extend(item.severity.clone());
+        }
+        Ok(settings)
+    }
+
+    /// Rejects codes in `enable`, `disable` and `severity`, at the top level
+    /// or in an override, that match no rule, such as a mistyped `TG05`.
+    fn check_rule_codes(&self, custom_rules: &HashSet<String>) -> Result<()> {
+        let built_in: HashSet<String> = registry().iter().map(|rule| rule.id().to_string()).collect();
+        let lists = std::iter::once(("", self.enable.as_deref(), &self.disable, &self.severity))
+            .chain(self.overrides.iter().map(|item| (" in overrides", item.enable.as_deref(), &item.disable, &item.severity)));
+        for (place, enable, disable, severity) in lists {
+            let codes = [("enable", enable.unwrap_or_default()), ("disable", disable.as_slice())]
+                .into_iter()
+                .flat_map(|(key, codes)| codes.iter().map(move |code| (key, code)))
+                .chain(severity.keys().map(|code| ("severity", code)));
+            for (key, code) in codes {
+                if !built_in.contains(code) && !custom_rules.contains(code) && code != UNUSED_SUPPRESSION {
+                    bail!("unknown rule code '{}' in {}{}", code, key, place);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The inline custom rules followed by those in `rule-files`, which are
+    /// relative to the configuration file.
+    pub fn custom_rules(&self) -> Result<Vec<RuleDefinition>> {
+        let mut rules = self.rules.clone();
+        for file in &self.rule_files {
+            let path = self.root.join(file);
+            let text = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            let parsed: Result<RuleFile> = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(Into::into),
+                _ => toml::from_str(&text).map_err(Into::into),
+            };
+            rules.extend(parsed.with_context(|| format!("invalid rules in {}", path.display()))?.rules);
+        }
+        Ok(rules)
+    }
+
+    /// Whether a file found while walking directories should be checked.
+    pub fn is_included(&self, path: &Path) -> Result<bool> {
+        let relative = self.relative(path);
+        let included = self.include.is_empty() || glob_set(&self.include)?.is_match(&relative);
+        Ok(included && !self.is_excluded(path)?)
+    }
+
+    /// Whether `path`, a file or directory, matches an `exclude` glob.
+    pub fn is_excluded(&self, path: &Path) -> Result<bool> {
+        Ok(glob_set(&self.exclude)?.is_match(self.relative(path)))
+    }
+
+    fn relative(&self, path: &Path) -> PathBuf {
+        let absolute = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let root = fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        absolute.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(absolute)
+    }
+}
+
+/// Resolves the configuration of each checked path: the one given with
+/// `--config`, or else the nearest discovered one, cached by directory.
+#[derive(Debug, Default)]
+pub struct ConfigResolver {
+    explicit: Option<Config>,
+    discovered: HashMap<PathBuf, Option<Config>>,
+}
+
+impl ConfigResolver {
+    pub fn new(explicit: Option<Config>) -> Self {
+        ConfigResolver {
+            explicit,
+            discovered: HashMap::new(),
+        }
+    }
+
+    pub fn config_for(&mut self, path: &Path) -> Result<Option<&Config>> {
+        if self.explicit.is_some() {
+            return Ok(self.explicit.as_ref());
+        }
+        let dir = if path.is_dir() { path } else { path.parent().unwrap_or(Path::new(".")) };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        if !self.discovered.contains_key(dir) {
+            self.discovered.insert(dir.to_path_buf(), Config::discover(dir)?);
+        }
+        Ok(self.discovered[dir].as_ref())
+    }
+
+    /// Settings for `path`; the defaults when no configuration applies, with
+    /// no custom rules since none was declared for it.
+    pub fn settings_for(&mut self, path: &Path) -> Result<Settings> {
+        match self.config_for(path)? {
+            Some(config) => {
+                let custom_rules = config.custom_rules()?.into_iter().map(|rule| rule.id).collect();
+                config.settings_for(Some(path), custom_rules)
+            }
+            None => Ok(Settings {
+                custom_rules: Some(HashSet::new()),
+                ..Settings::default()
+            }),
+        }
+    }
+
+    /// Custom rules of the explicit configuration, or of every one
+    /// discovered so far. A rule id defined twice keeps its first definition,
+    /// ordering configurations by directory.
+    pub fn custom_rules(&self) -> Result<Vec<RuleDefinition>> {
+        let mut configs: Vec<&Config> = match &self.explicit {
+            Some(config) => vec![config],
+            None => self.discovered.values().flatten().collect(),
+        };
+        configs.sort_by(|a, b| a.root.cmp(&b.root));
+        configs.dedup_by(|a, b| a.root == b.root);
+        let mut rules: Vec<RuleDefinition> = Vec::new();
+        for config in configs {
+            for rule in config.custom_rules()? {
+                if !rules.iter().any(|existing| existing.id == rule.id) {
+                    rules.push(rule);
+                }
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn is_included(&mut self, path: &Path) -> Result<bool> {
+        match self.config_for(path)? {
+            Some(config) => config.is_included(path),
+            None => Ok(true),
+        }
+    }
+
+    pub fn is_excluded(&mut self, path: &Path) -> Result<bool> {
+        match self.config_for(path)? {
+            Some(config) => config.is_excluded(path),
+            None => Ok(false),
+        }
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("invalid glob '{}'", pattern))?);
+        // A directory pattern such as `build` also covers everything below it.
+        builder.add(Glob::new(&format!("{}/**", pattern.trim_end_matches('/')))?);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,128 @@
+use super::*;
+use crate::test_util::scratch_dir;
+
+#[test]
+fn test_parse_config() -> Result<()> {
+    let config: Config = toml::from_str(
+        r#"
+        disable = ["TG005"]
+        torch-version = "2.1.0"
+
+        [severity]
+        TG009 = "warning"
+
+        [[overrides]]
+        paths = ["notebooks"]
+        disable = ["TG001"]
+        severity = { TG009 = "hint" }
+        "#,
+    )?;
+    assert_eq!(config.torch_version, Some(TorchVersion::new(2, 1)));
+    assert_eq!(config.severity["TG009"], Severity::Warning);
+
+    let settings = config.settings_for(Some(Path::new("train.py")), HashSet::new())?;
+    assert!(!settings.is_enabled("TG005"));
+    assert!(settings.is_enabled("TG001"));
+    assert_eq!(settings.severity["TG009"], Severity::Warning);
+
+    let notebook = config.settings_for(Some(Path::new("notebooks/explore.py")), HashSet::new())?;
+    assert!(!notebook.is_enabled("TG001"));
+    assert!(!notebook.is_enabled("TG005"));
+    assert_eq!(notebook.severity["TG009"], Severity::Hint);
+    Ok(())
+}
+
+#[test]
+fn test_unknown_keys_rejected() {
+    assert!(toml::from_str::<Config>("disabled = [\"TG005\"]").is_err());
+    assert!(toml::from_str::<Config>("[severity]\nTG001 = \"fatal\"").is_err());
+}
+
+#[test]
+fn test_unknown_rule_codes_rejected() -> Result<()> {
+    let error = |text: &str| toml::from_str::<Config>(text).unwrap().settings_for(None, HashSet::new()).unwrap_err().to_string();
+    assert_eq!(error("disable = [\"TG05\"]"), "unknown rule code 'TG05' in disable");
+    assert_eq!(error("enable = [\"TG001\", \"TG1\"]"), "unknown rule code 'TG1' in enable");
+    assert_eq!(error("[[overrides]]\npaths = [\"a\"]\nseverity = { TG00 = \"hint\" }"), "unknown rule code 'TG00' in severity in overrides");
+
+    let config: Config = toml::from_str(
+        r#"
+        disable = ["TG100", "ACME001"]
+
+        [[rules]]
+        id = "ACME001"
+        query = "(call) @target"
+        message = "call"
+        "#,
+    )?;
+    assert!(!config.settings_for(None, ["ACME001".to_string()].into())?.is_enabled("ACME001"));
+    Ok(())
+}
+
+#[test]
+fn test_discover_walks_up() -> Result<()> {
+    let dir = scratch_dir("discover");
+    fs::create_dir_all(dir.join("project/src/models"))?;
+    fs::write(dir.join("project/pyproject.toml"), "[tool.torchguard]\ndisable = [\"TG003\"]\n")?;
+    fs::write(dir.join("project/src/models/net.py"), "")?;
+
+    let config = Config::discover(&dir.join("project/src/models/net.py"))?.expect("config found");
+    assert_eq!(config.disable, vec!["TG003"]);
+    assert_eq!(fs::canonicalize(&config.root)?, fs::canonicalize(dir.join("project"))?);
+
+    fs::write(dir.join("project/src/torchguard.toml"), "disable = [\"TG004\"]\n")?;
+    let config = Config::discover(&dir.join("project/src/models/net.py"))?.expect("config found");
+    assert_eq!(config.disable, vec!["TG004"]);
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_pyproject_without_table_is_skipped() -> Result<()> {
+    let dir = scratch_dir("pyproject");
+    fs::create_dir_all(dir.join("inner"))?;
+    fs::write(dir.join("torchguard.toml"), "exclude = [\"build\"]\n")?;
+    fs::write(dir.join("inner/pyproject.toml"), "[project]\nname = \"demo\"\n")?;
+    fs::write(dir.join("inner/train.py"), "")?;
+
+    let config = Config::discover(&dir.join("inner/train.py"))?.expect("config found");
+    assert_eq!(config.exclude, vec!["build"]);
+    assert!(config.is_excluded(&dir.join("build/gen.py"))?);
+    assert!(config.is_included(&dir.join("inner/train.py"))?);
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_custom_rules_from_rule_files() -> Result<()> {
+    let dir = scratch_dir("rule-files");
+    fs::write(
+        dir.join(CONFIG_FILE),
+        r#"
+rule-files = ["rules.yaml"]
+
+[[rules]]
+id = "ACME001"
+query = "(call) @target"
+message = "inline"
+"#,
+    )?;
+    fs::write(
+        dir.join("rules.yaml"),
+        "rules:\n  - id: ACME002\n    query: \"(identifier) @target\"\n    message: from yaml\n    severity: error\n",
+    )?;
+    let config = Config::load(&dir.join(CONFIG_FILE))?;
+    let rules = config.custom_rules()?;
+    let ids: Vec<&str> = rules.iter().map(|rule| rule.id.as_str()).collect();
+    assert_eq!(ids, ["ACME001", "ACME002"]);
+    assert_eq!(rules[0].severity, Severity::Warning);
+    assert_eq!(rules[1].severity, Severity::Error);
+
+    fs::write(dir.join("rules.yaml"), "rules:\n  - id: ACME002\n    querry: \"(call) @target\"\n")?;
+    let error = Config::load(&dir.join(CONFIG_FILE))?.custom_rules().unwrap_err();
+    assert!(format!("{:#}", error).contains("rules.yaml"));
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
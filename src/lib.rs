@@ -0,0 +1,12 @@
+pub mod analyzer;
+pub mod api;
+pub mod baseline;
+pub mod changes;
+pub mod cli;
+pub mod config;
+pub mod notebook;
+pub mod sarif;
+pub mod search;
+
+#[cfg(test)]
+mod test_util;
@@ -1,23 +1,34 @@
-mod analyzer;
-mod search;
-mod api;
+use std::process::ExitCode;
 
-use colored::*;
-use tracing_subscriber;
 use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use rust_llm_qdrant::api;
+use rust_llm_qdrant::cli::{self, BaselineCommand, Cli, Command, DEFAULT_PORT};
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+async fn main() -> Result<ExitCode> {
+    // Initialize logging; stdout is reserved for command output
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
 
+    match Cli::parse().command {
+        Some(Command::Check(args)) => cli::check(&args),
+        Some(Command::Serve(args)) => serve(args.port).await,
+        Some(Command::Baseline(args)) => match args.command {
+            BaselineCommand::Create(args) => cli::create_baseline(&args),
+        },
+        None => serve(DEFAULT_PORT).await,
+    }
+}
+
+async fn serve(port: u16) -> Result<ExitCode> {
     println!("{}", "ML Code Assistant".bright_green().bold());
     println!("{}", "Starting web server...".yellow());
 
     // Start web server
     let app = api::create_api().await;
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3003").await?;
-    println!("{}", "✓ Server running at http://localhost:3003".green());
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    println!("{}", format!("✓ Server running at http://localhost:{}", port).green());
     println!("{}", "API Endpoints:".bright_blue());
     println!("  POST /analyze - Analyze code for optimization opportunities");
     println!("  POST /search  - Search for similar code patterns");
@@ -25,7 +36,5 @@ async fn main() -> Result<()> {
 
     axum::serve(listener, app).await?;
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
-
-// Rest of the code remains the same
@@ -0,0 +1,230 @@
+//! Jupyter notebooks. The code cells are concatenated into one Python source
+//! for analysis, and findings and fixes are mapped back onto the cells.
+//!
+//! Line magics (`%time ...`) and shell escapes (`!pip ...`) are turned into
+//! comments while analyzing and restored when writing the notebook back.
+//! Cells starting with a cell magic (`%%bash`) are not Python and are skipped.
+
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::analyzer::{select_edits, AppliedFixes, Applicability, CodeAnalyzer, Edit, Finding, Span, MAX_FIX_PASSES};
+
+pub const EXTENSION: &str = "ipynb";
+
+#[derive(Debug)]
+struct Cell {
+    /// Position in the notebook's `cells` array.
+    index: usize,
+    /// Bytes of the combined source holding this cell.
+    range: Range<usize>,
+    /// Masked magic lines and the originals they replace.
+    magics: Vec<(String, String)>,
+    /// The source was a plain string rather than a list of lines.
+    source_is_string: bool,
+    trailing_newline: bool,
+}
+
+#[derive(Debug)]
+pub struct Notebook {
+    document: Value,
+    indent: usize,
+    cells: Vec<Cell>,
+    source: String,
+}
+
+impl Notebook {
+    pub fn parse(text: &str) -> Result<Self> {
+        let document: Value = serde_json::from_str(text).context("invalid notebook JSON")?;
+        let Some(raw_cells) = document.get("cells").and_then(Value::as_array) else {
+            bail!("not a notebook: missing `cells`");
+        };
+
+        let mut cells = Vec::new();
+        let mut texts = Vec::new();
+        for (index, cell) in raw_cells.iter().enumerate() {
+            if cell.get("cell_type").and_then(Value::as_str) != Some("code") {
+                continue;
+            }
+            let (text, source_is_string) = match cell.get("source") {
+                Some(Value::String(text)) => (text.clone(), true),
+                Some(Value::Array(lines)) => (lines.iter().filter_map(Value::as_str).collect(), false),
+                _ => continue,
+            };
+            if text.trim_start().starts_with("%%") {
+                continue;
+            }
+
+            let mut magics = Vec::new();
+            let mut masked = String::with_capacity(text.len() + 1);
+            for line in text.split_inclusive('\n') {
+                let content = line.trim_end_matches('\n');
+                let trimmed = content.trim_start();
+                if trimmed.starts_with(['%', '!']) {
+                    let comment = format!("{}#{}", &content[..content.len() - trimmed.len()], &trimmed[1..]);
+                    masked.push_str(&comment);
+                    masked.push_str(&line[content.len()..]);
+                    magics.push((comment, content.to_string()));
+                } else {
+                    masked.push_str(line);
+                }
+            }
+            let trailing_newline = masked.is_empty() || masked.ends_with('\n');
+            if !trailing_newline {
+                masked.push('\n');
+            }
+            cells.push(Cell {
+                index,
+                range: 0..0,
+                magics,
+                source_is_string,
+                trailing_newline,
+            });
+            texts.push(masked);
+        }
+
+        // Keep the file's indentation (Jupyter writes one space) when saving.
+        let indent = text
+            .lines()
+            .nth(1)
+            .map_or(1, |line| line.len() - line.trim_start_matches(' ').len())
+            .max(1);
+        let mut notebook = Notebook {
+            document,
+            indent,
+            cells,
+            source: String::new(),
+        };
+        notebook.layout(texts);
+        Ok(notebook)
+    }
+
+    /// The code cells as one Python source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The analyzed source of cell `cell`, counted from 1 like [`Finding::cell`].
+    pub fn cell_source(&self, cell: usize) -> Option<&str> {
+        let cell = self.cells.iter().find(|c| c.index + 1 == cell)?;
+        Some(&self.source[cell.range.clone()])
+    }
+
+    /// Rewrites a finding on [`Notebook::source`] to be relative to its cell.
+    /// The fix is dropped, as its offsets only make sense in the combined source.
+    pub fn localize(&self, mut finding: Finding) -> Finding {
+        let Some(cell) = self.cell_at(finding.span.start_byte).map(|i| &self.cells[i]) else {
+            return finding;
+        };
+        let text = &self.source[cell.range.clone()];
+        let start = finding.span.start_byte - cell.range.start;
+        let end = finding.span.end_byte.min(cell.range.end) - cell.range.start;
+        finding.span = Span::from_bytes(text, start, end);
+        finding.line = finding.span.start_line as i32;
+        finding.cell = Some(cell.index + 1);
+        finding.fix = None;
+        finding
+    }
+
+    /// Applies fixes until the cells stop changing. Fixes spanning more than
+    /// one cell are left alone. Returns whether anything changed.
+    pub fn fix(&mut self, analyzer: &mut CodeAnalyzer, include_unsafe: bool) -> Result<bool> {
+        let original = self.source.clone();
+        let mut applied = AppliedFixes::default();
+        for _ in 0..MAX_FIX_PASSES {
+            let findings = analyzer.analyze(&self.source)?;
+            let candidates: Vec<&Finding> = findings
+                .iter()
+                .filter(|finding| {
+                    finding.fix.as_ref().is_some_and(|fix| {
+                        (include_unsafe || fix.applicability == Applicability::Safe)
+                            && fix.edits.iter().all(|edit| self.within_cell(edit))
+                    })
+                })
+                .filter(|finding| applied.is_new(&self.source, finding))
+                .collect();
+            let edits = select_edits(candidates.iter().filter_map(|finding| finding.fix.as_ref()));
+            applied.record(&self.source, &candidates, &edits);
+            let before = self.source.clone();
+            self.apply_edits(&edits);
+            if self.source == before {
+                break;
+            }
+        }
+        Ok(self.source != original)
+    }
+
+    /// The notebook as JSON, with the cells' current sources. Outputs and
+    /// metadata are kept as they were.
+    pub fn to_json(&self) -> Result<String> {
+        let mut document = self.document.clone();
+        for cell in &self.cells {
+            let mut magics = cell.magics.iter().peekable();
+            let mut text = String::new();
+            for line in self.source[cell.range.clone()].split_inclusive('\n') {
+                let content = line.trim_end_matches('\n');
+                match magics.peek() {
+                    Some((masked, original)) if masked == content => {
+                        text.push_str(original);
+                        text.push_str(&line[content.len()..]);
+                        magics.next();
+                    }
+                    _ => text.push_str(line),
+                }
+            }
+            if !cell.trailing_newline && text.ends_with('\n') {
+                text.pop();
+            }
+            document["cells"][cell.index]["source"] = if cell.source_is_string {
+                Value::String(text)
+            } else {
+                text.split_inclusive('\n').map(|line| Value::String(line.to_string())).collect()
+            };
+        }
+
+        let indent = " ".repeat(self.indent);
+        let mut out = Vec::new();
+        let mut serializer =
+            serde_json::Serializer::with_formatter(&mut out, serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes()));
+        document.serialize(&mut serializer)?;
+        out.push(b'\n');
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn apply_edits(&mut self, edits: &[&Edit]) {
+        let mut texts: Vec<String> = self.cells.iter().map(|cell| self.source[cell.range.clone()].to_string()).collect();
+        // Back to front, so earlier offsets stay valid.
+        for edit in edits.iter().rev() {
+            let Some(i) = self.cell_at(edit.span.start_byte) else { continue };
+            let offset = self.cells[i].range.start;
+            texts[i].replace_range(edit.span.start_byte - offset..edit.span.end_byte - offset, &edit.replacement);
+        }
+        self.layout(texts);
+    }
+
+    fn layout(&mut self, texts: Vec<String>) {
+        self.source.clear();
+        for (cell, text) in self.cells.iter_mut().zip(texts) {
+            let start = self.source.len();
+            self.source.push_str(&text);
+            cell.range = start..self.source.len();
+        }
+    }
+
+    /// The cell holding `byte`. A position on a boundary belongs to the later
+    /// cell, so insertions there start that cell.
+    fn cell_at(&self, byte: usize) -> Option<usize> {
+        self.cells.iter().rposition(|cell| cell.range.start <= byte)
+    }
+
+    fn within_cell(&self, edit: &Edit) -> bool {
+        self.cell_at(edit.span.start_byte)
+            .is_some_and(|i| edit.span.end_byte <= self.cells[i].range.end)
+    }
+}
+
+#[cfg(test)]
+mod tests;
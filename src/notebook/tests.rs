@@ -0,0 +1,90 @@
+use super::*;
+
+const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["# Training\n"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {"scrolled": true},
+   "outputs": [{"name": "stdout", "output_type": "stream", "text": ["ok\n"]}],
+   "source": ["%matplotlib inline\n", "!pip install torch\n", "import torch"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 2,
+   "metadata": {},
+   "outputs": [],
+   "source": "%%bash\nnvidia-smi"
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 3,
+   "metadata": {},
+   "outputs": [],
+   "source": ["x = 1\n", "data = data.cuda()\n"]
+  }
+ ],
+ "metadata": {"kernelspec": {"name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+#[test]
+fn test_concatenates_code_cells() -> Result<()> {
+    let notebook = Notebook::parse(NOTEBOOK)?;
+    assert_eq!(notebook.source(), "#matplotlib inline\n#pip install torch\nimport torch\nx = 1\ndata = data.cuda()\n");
+    assert_eq!(notebook.cell_source(4), Some("x = 1\ndata = data.cuda()\n"));
+    assert_eq!(notebook.cell_source(3), None, "cell magics are skipped");
+    assert!(Notebook::parse("{}").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_findings_are_relative_to_cells() -> Result<()> {
+    let notebook = Notebook::parse(NOTEBOOK)?;
+    let findings = CodeAnalyzer::new()?.analyze(notebook.source())?;
+    let finding = notebook.localize(findings.into_iter().find(|f| f.rule_id == "TG001").unwrap());
+    assert_eq!(finding.cell, Some(4));
+    assert_eq!((finding.line, finding.span.start_column), (2, 8));
+    assert!(finding.fix.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_fix_round_trips_notebook() -> Result<()> {
+    let mut notebook = Notebook::parse(NOTEBOOK)?;
+    assert!(notebook.fix(&mut CodeAnalyzer::new()?, false)?);
+    let fixed: Value = serde_json::from_str(&notebook.to_json()?)?;
+    let original: Value = serde_json::from_str(NOTEBOOK)?;
+
+    assert_eq!(fixed["cells"][1]["source"][0], "%matplotlib inline\n");
+    assert_eq!(fixed["cells"][1]["source"][1], "!pip install torch\n");
+    assert_eq!(fixed["cells"][1]["source"].as_array().unwrap().len(), 3, "no trailing newline added");
+    let code = |cell: &Value| cell["source"].as_array().unwrap().iter().filter_map(Value::as_str).collect::<String>();
+    let fixed_code = format!("{}{}", code(&fixed["cells"][1]), code(&fixed["cells"][3]));
+    assert!(fixed_code.contains("data = data.to(device)\n"), "{}", fixed_code);
+    assert!(fixed_code.contains("device = "), "{}", fixed_code);
+
+    for key in ["outputs", "metadata", "execution_count"] {
+        assert_eq!(fixed["cells"][1][key], original["cells"][1][key]);
+    }
+    assert_eq!(fixed["cells"][2], original["cells"][2]);
+    assert_eq!(fixed["metadata"], original["metadata"]);
+    Ok(())
+}
+
+#[test]
+fn test_unchanged_notebook_keeps_cells() -> Result<()> {
+    let text = r#"{"cells": [{"cell_type": "code", "metadata": {}, "outputs": [], "source": "import torch"}], "metadata": {}, "nbformat": 4, "nbformat_minor": 5}"#;
+    let mut notebook = Notebook::parse(text)?;
+    assert!(!notebook.fix(&mut CodeAnalyzer::new()?, true)?);
+    let saved: Value = serde_json::from_str(&notebook.to_json()?)?;
+    assert_eq!(saved, serde_json::from_str::<Value>(text)?);
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use serde_json::{json, Value};
+
+use crate::analyzer::rules::Rule;
+use crate::analyzer::{Applicability, Finding, Severity, Span, UnusedSuppression};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/richiectr360/TorchGuard";
+
+/// Findings for one analyzed file, keyed by the URI reported in SARIF.
+pub struct FileFindings<'a> {
+    pub uri: String,
+    pub findings: &'a [Finding],
+}
+
+/// Builds a SARIF 2.1.0 log with a single run. `rules` becomes the driver's
+/// rule table, followed by TG100, and results point into it by index. Only
+/// safe fixes are exported, since consumers may apply them without review.
+pub fn to_sarif(rules: &[Box<dyn Rule>], files: &[FileFindings]) -> Value {
+    let rules: Vec<&dyn Rule> = rules.iter().map(|rule| rule.as_ref()).chain([&UnusedSuppression as &dyn Rule]).collect();
+    let descriptors: Vec<Value> = rules.iter().map(|rule| rule_descriptor(*rule)).collect();
+
+    let mut results = Vec::new();
+    for file in files {
+        for finding in file.findings {
+            let rule_index = rules.iter().position(|rule| rule.id() == finding.rule_id);
+            results.push(result(&file.uri, finding, rule_index));
+        }
+    }
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "TorchGuard",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": descriptors,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+fn rule_descriptor(rule: &dyn Rule) -> Value {
+    json!({
+        "id": rule.id(),
+        "shortDescription": { "text": rule.summary() },
+        "helpUri": rule.doc_url(),
+        "defaultConfiguration": { "level": level(rule.severity()) },
+        "properties": { "category": rule.category() },
+    })
+}
+
+fn result(uri: &str, finding: &Finding, rule_index: Option<usize>) -> Value {
+    let mut result = json!({
+        "ruleId": finding.rule_id,
+        "level": level(finding.severity),
+        "message": { "text": finding.message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri },
+                "region": region(&finding.span),
+            }
+        }],
+    });
+    if let Some(index) = rule_index {
+        result["ruleIndex"] = json!(index);
+    }
+    if let Some(cell) = finding.cell {
+        // Lines are relative to the notebook cell; byte offsets into the
+        // file would be meaningless.
+        if let Some(region) = result["locations"][0]["physicalLocation"]["region"].as_object_mut() {
+            region.remove("byteOffset");
+            region.remove("byteLength");
+        }
+        result["properties"] = json!({ "cell": cell });
+    }
+    if let Some(fix) = finding.fix.as_ref().filter(|fix| fix.applicability == Applicability::Safe) {
+        let replacements: Vec<Value> = fix
+            .edits
+            .iter()
+            .map(|edit| {
+                json!({
+                    "deletedRegion": region(&edit.span),
+                    "insertedContent": { "text": edit.replacement },
+                })
+            })
+            .collect();
+        result["fixes"] = json!([{
+            "description": { "text": fix.description },
+            "artifactChanges": [{
+                "artifactLocation": { "uri": uri },
+                "replacements": replacements,
+            }],
+        }]);
+    }
+    result
+}
+
+fn region(span: &Span) -> Value {
+    json!({
+        "startLine": span.start_line,
+        "startColumn": span.start_column,
+        "endLine": span.end_line,
+        "endColumn": span.end_column,
+        "byteOffset": span.start_byte,
+        "byteLength": span.end_byte - span.start_byte,
+    })
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Hint => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+use super::*;
+use crate::analyzer::{rules, CodeAnalyzer, Settings};
+use anyhow::Result;
+
+#[test]
+fn test_sarif_log_structure() -> Result<()> {
+    let code = "import torch\nnet = model.cuda()\nloader = DataLoader(ds, batch_size=30, num_workers=2, persistent_workers=True)\n";
+    let findings = CodeAnalyzer::new()?.analyze(code)?;
+    let rules = rules::registry();
+    let log = to_sarif(&rules, &[FileFindings { uri: "src/train.py".into(), findings: &findings }]);
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    // The registry's rules, then TG100.
+    assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), rules.len() + 1);
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    let cuda = &results[0];
+    assert_eq!(cuda["ruleId"], "TG001");
+    assert_eq!(cuda["level"], "warning");
+    assert_eq!(run["tool"]["driver"]["rules"][cuda["ruleIndex"].as_u64().unwrap() as usize]["id"], "TG001");
+    let location = &cuda["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/train.py");
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 7);
+    let replacement = &cuda["fixes"][0]["artifactChanges"][0]["replacements"][0];
+    assert_eq!(replacement["insertedContent"]["text"], "model.to(device)");
+
+    let batch_size = &results[1];
+    assert_eq!(batch_size["ruleId"], "TG005");
+    assert_eq!(batch_size["level"], "note");
+    assert!(batch_size.get("fixes").is_none());
+    Ok(())
+}
+
+#[test]
+fn test_sarif_describes_unused_suppressions() -> Result<()> {
+    let mut analyzer = CodeAnalyzer::new()?;
+    analyzer.set_settings(Settings {
+        report_unused_suppressions: true,
+        ..Settings::default()
+    });
+    let findings = analyzer.analyze("x = 1  # torchguard: ignore[TG001]\n")?;
+    let log = to_sarif(analyzer.rules(), &[FileFindings { uri: "a.py".into(), findings: &findings }]);
+    let run = &log["runs"][0];
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "TG100");
+    let descriptor = &run["tool"]["driver"]["rules"][result["ruleIndex"].as_u64().unwrap() as usize];
+    assert_eq!(descriptor["id"], "TG100");
+    assert_eq!(descriptor["properties"]["category"], "Suppression");
+    Ok(())
+}
+
+#[test]
+fn test_sarif_omits_unsafe_fixes() -> Result<()> {
+    let findings = CodeAnalyzer::new()?.analyze("x = data.cuda(0)\n")?;
+    let log = to_sarif(&rules::registry(), &[FileFindings { uri: "a.py".into(), findings: &findings }]);
+    assert!(log["runs"][0]["results"][0].get("fixes").is_none());
+    Ok(())
+}
@@ -3,6 +3,7 @@ use reqwest::Client;
 use serde_json::json;
 
 #[tokio::test]
+#[ignore = "requires a running TorchGuard server"]
 async fn test_analyze_endpoint() -> Result<()> {
     let client = Client::new();
     let code = r#"
@@ -38,6 +39,7 @@ def train_model(model, data):
 }
 
 #[tokio::test]
+#[ignore = "requires a running TorchGuard server"]
 async fn test_search_endpoint() -> Result<()> {
     let client = Client::new();
     let query = r#"
@@ -64,6 +66,7 @@ def predict(model, data):
 }
 
 #[tokio::test]
+#[ignore = "requires a running TorchGuard server"]
 async fn test_health_endpoint() -> Result<()> {
     let client = Client::new();
     let response = client
@@ -76,3 +79,22 @@ async fn test_health_endpoint() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[ignore = "requires a running TorchGuard server"]
+async fn test_analyze_rejects_rule_files() -> Result<()> {
+    let response = Client::new()
+        .post("http://localhost:3001/analyze")
+        .json(&json!({
+            "code": "x = 1\n",
+            "config": { "rule-files": ["/etc/passwd"] }
+        }))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body = response.text().await?;
+    assert!(body.contains("rule-files is not supported"));
+    assert!(!body.contains("root:"), "the rule file must not be read");
+    Ok(())
+}
@@ -0,0 +1,8 @@
+import torch
+
+
+def predict(model, data, device):
+    model.to(device)
+    model.eval()
+    with torch.no_grad():
+        return model(data.to(device))
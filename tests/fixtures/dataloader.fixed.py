@@ -0,0 +1,54 @@
+import torch
+from torch import nn
+from torch.utils.data import DataLoader
+
+
+class Net(nn.Module):
+    def __init__(self):
+        super().__init__()
+        self.fc = nn.Linear(16, 16)
+        self.bn = nn.BatchNorm1d(16)
+
+    def forward(self, x):
+        return self.bn(self.fc(x))
+
+
+def collate(batch):
+    return torch.stack(batch)
+
+
+def make_loader():
+    return DataLoader(train_set, batch_size=32, num_workers=2, persistent_workers=True, pin_memory=True)  # expect: TG027
+
+
+def export_loader():
+    return DataLoader(train_set, batch_size=32, num_workers=2, persistent_workers=True)
+
+
+train_loader = DataLoader(train_set, batch_size=32, shuffle=True, num_workers=4, persistent_workers=True, pin_memory=True, drop_last=True)  # expect: TG027, TG028, TG030
+val_loader = DataLoader(val_set, batch_size=32, shuffle=False, num_workers=4, persistent_workers=True, pin_memory=True)  # expect: TG027, TG029
+test_loader = DataLoader(test_set, batch_size=32, prefetch_factor=4)  # expect: TG004, TG031
+lambda_loader = DataLoader(train_set, batch_size=32, num_workers=2, persistent_workers=True, collate_fn=lambda b: b)  # expect: TG028, TG032
+single_loader = DataLoader(train_set, batch_size=32, num_workers=0, collate_fn=lambda b: b, prefetch_factor=2)  # expect: TG031
+eval_loader = DataLoader(test_set, batch_size=32, prefetch_factor=None)  # expect: TG004
+tuned_loader = DataLoader(
+    train_set,
+    batch_size=32,
+    shuffle=True,
+    num_workers=4,
+    persistent_workers=True,
+    pin_memory=True,
+    drop_last=True,
+    collate_fn=collate,
+)
+
+device = torch.device("cuda")
+model = Net().to(device)
+for x, y in train_loader:
+    x, y = x.to(device), y.to(device)
+for x, y in val_loader:
+    x = x.to(device)
+for x, y in tuned_loader:
+    x = x.to(device)
+for x in make_loader():
+    x = x.to(device)
@@ -0,0 +1,15 @@
+"""Setup helpers; the device line goes below the docstring and imports."""
+import torch
+import torch.nn as nn
+device = torch.device('cuda' if torch.cuda.is_available() else 'cpu')
+
+
+class Net(nn.Module):
+    pass
+
+
+def setup(data):
+    model = Net().to(device)  # expect: TG001
+    model = model.to(device)  # expect: TG011
+    batch = data.to(device).to(device)  # expect: TG001
+    return model, batch
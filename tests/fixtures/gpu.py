@@ -0,0 +1,14 @@
+"""Setup helpers; the device line goes below the docstring and imports."""
+import torch
+import torch.nn as nn
+
+
+class Net(nn.Module):
+    pass
+
+
+def setup(data):
+    model = Net().cuda()  # expect: TG001
+    model = model.to(device)  # expect: TG011
+    batch = data.cuda(0).cuda()  # expect: TG001
+    return model, batch
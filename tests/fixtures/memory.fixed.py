@@ -0,0 +1,56 @@
+import torch
+
+
+def train(model, loader, optimizer, criterion):
+    model.train()
+    total_loss = 0
+    total_detached = 0
+    total_items = 0
+    outputs = []
+    losses = []
+    scaler = torch.cuda.amp.GradScaler()
+    for x, y in loader:
+        optimizer.zero_grad()
+        with torch.autocast(device_type='cuda'):
+            out = model(x)
+            loss = criterion(out, y)
+        scaler.scale(loss).backward(retain_graph=True)  # expect: TG006, TG008, TG023
+        scaler.step(optimizer)
+        scaler.update()  # expect: TG009
+        total_loss += loss.detach()  # expect: TG022
+        total_loss = total_loss + (loss * x.size(0)).detach()  # expect: TG022
+        total_detached += loss.detach()
+        total_items += loss.item()  # expect: TG019
+        outputs.append(out.detach())  # expect: TG024
+        losses.append(loss.detach())
+        batch = []
+        batch.append(out)
+        with torch.no_grad():
+            outputs.append(model(x))
+    return total_loss, outputs, losses
+
+
+def accumulate(model, loader, optimizer, criterion, steps):
+    model.train()
+    accumulated = 0
+    scaler = torch.cuda.amp.GradScaler()
+    for i, (x, y) in enumerate(loader):
+        accumulated += criterion(model(x), y) / steps
+        if i % steps == 0:
+            scaler.scale(accumulated).backward()  # expect: TG006, TG008
+            scaler.step(optimizer)
+            scaler.update()  # expect: TG009
+            optimizer.zero_grad()
+            accumulated = 0
+
+
+def two_losses(model, loader, optimizer, criterion, penalty):
+    model.train()
+    scaler = torch.cuda.amp.GradScaler()
+    for x, y in loader:
+        optimizer.zero_grad()
+        out = model(x)
+        scaler.scale(criterion(out, y)).backward(retain_graph=True)  # expect: TG006, TG008
+        scaler.scale(penalty(out)).backward()  # expect: TG006, TG008
+        scaler.step(optimizer)
+        scaler.update()  # expect: TG009
@@ -0,0 +1,49 @@
+import torch
+
+
+def train(model, loader, optimizer, criterion):
+    model.train()
+    total_loss = 0
+    total_detached = 0
+    total_items = 0
+    outputs = []
+    losses = []
+    for x, y in loader:
+        optimizer.zero_grad()
+        out = model(x)
+        loss = criterion(out, y)
+        loss.backward(retain_graph=True)  # expect: TG006, TG008, TG023
+        optimizer.step()  # expect: TG009
+        total_loss += loss  # expect: TG022
+        total_loss = total_loss + loss * x.size(0)  # expect: TG022
+        total_detached += loss.detach()
+        total_items += loss.item()  # expect: TG019
+        outputs.append(out)  # expect: TG024
+        losses.append(loss.detach())
+        batch = []
+        batch.append(out)
+        with torch.no_grad():
+            outputs.append(model(x))
+    return total_loss, outputs, losses
+
+
+def accumulate(model, loader, optimizer, criterion, steps):
+    model.train()
+    accumulated = 0
+    for i, (x, y) in enumerate(loader):
+        accumulated += criterion(model(x), y) / steps
+        if i % steps == 0:
+            accumulated.backward()  # expect: TG006, TG008
+            optimizer.step()  # expect: TG009
+            optimizer.zero_grad()
+            accumulated = 0
+
+
+def two_losses(model, loader, optimizer, criterion, penalty):
+    model.train()
+    for x, y in loader:
+        optimizer.zero_grad()
+        out = model(x)
+        criterion(out, y).backward(retain_graph=True)  # expect: TG006, TG008
+        penalty(out).backward()  # expect: TG006, TG008
+        optimizer.step()  # expect: TG009
@@ -0,0 +1,16 @@
+import torch
+
+
+def do_step(optimizer):
+    torch.nn.utils.clip_grad_norm_(model.parameters(), 1.0)
+    optimizer.step()  # expect: TG009, TG010
+
+
+def train(model, loader, optimizer, scheduler):
+    model.train()
+    for x, y in loader:
+        optimizer.zero_grad()
+        loss = model(x).mean()
+        loss.backward()  # expect: TG006
+        do_step(optimizer)
+    scheduler.step()
@@ -0,0 +1,55 @@
+import torch
+
+
+def test(net, test_data):
+    return net.forward(test_data)  # expect: TG002, TG007, TG026
+
+
+def train_step(model, data):
+    model(data).backward()  # expect: TG003, TG006, TG008
+
+
+def evaluate(model, loader):
+    correct = 0
+    for x, y in loader:
+        out = model(x)  # expect: TG002, TG007
+        correct += (out.argmax(1) == y).sum()
+        model(x)
+    return correct
+
+
+@torch.inference_mode()
+def validate(model, loader):
+    model.eval()
+    for x, y in loader:
+        model(x)
+        model.train()  # expect: TG025
+        model(x)
+
+
+def predict_proba(model, batch):
+    model.eval()
+    with torch.no_grad():
+        logits = model(batch)
+    return logits.softmax(-1)
+
+
+def fit(model, train_loader, val_loader, optimizer, criterion):
+    for epoch in range(10):
+        model.train()
+        for x, y in train_loader:
+            optimizer.zero_grad()
+            criterion(model(x), y).backward()  # expect: TG006, TG008
+            optimizer.step()  # expect: TG009
+        model.eval()
+        for x, y in val_loader:
+            model(x)  # expect: TG002
+        for x, y in val_loader:
+            with torch.no_grad():
+                model(x)
+
+
+class Classifier(torch.nn.Module):
+    def validation_step(self, batch, batch_idx):
+        x, y = batch
+        return self.model(x)
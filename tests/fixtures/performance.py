@@ -0,0 +1,6 @@
+import torch
+from torch.utils.data import DataLoader
+
+
+train_loader = DataLoader(train_set, batch_size=100)  # expect: TG004, TG005
+val_loader = DataLoader(val_set, batch_size=64, num_workers=4)  # expect: TG028
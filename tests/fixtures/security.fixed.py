@@ -0,0 +1,23 @@
+import pickle
+
+import torch
+
+
+def restore(model, path, config_text, payload, checkpoint_path):
+    model.load_state_dict(torch.load(path, weights_only=True))  # expect: TG015
+    state_dict = torch.load(path, map_location="cpu", weights_only=True)  # expect: TG015
+    model.load_state_dict(state_dict)
+    checkpoint = torch.load(path)  # expect: TG015
+    safe = torch.load(path, weights_only=True)
+    weights = torch.load(path, weights_only=True)  # expect: TG015
+    model.load_state_dict(weights)
+    with open("model.pkl", "rb") as f:
+        legacy = pickle.load(f)  # expect: TG016
+    with open(path, "rb") as f:
+        vocab = pickle.load(f)
+    cached = pickle.loads(payload)
+    old_weights = pickle.load(open(checkpoint_path, "rb"))  # expect: TG016
+    hub_model = torch.hub.load("org/repo", "resnet", trust_repo=True)  # expect: TG017
+    settings = eval(config_text)  # expect: TG018
+    constant = eval("1 + 1")
+    return checkpoint, safe, legacy, vocab, cached, old_weights, hub_model, settings, constant
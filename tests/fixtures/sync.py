@@ -0,0 +1,34 @@
+import torch
+
+
+def train(model, loader, optimizer, criterion):
+    running = torch.zeros(1, device="cuda")
+    all_labels = []
+    for step, (x, y) in enumerate(loader):
+        optimizer.zero_grad()
+        out = model(x)
+        loss = criterion(out, y)
+        loss.backward()  # expect: TG006, TG008
+        optimizer.step()  # expect: TG009
+        total = loss.item()  # expect: TG019
+        preds = out.argmax(1).cpu().numpy()  # expect: TG019
+        print(loss)  # expect: TG020
+        print(f"step {step}: {loss}")  # expect: TG020
+        value = float(loss)  # expect: TG020
+        torch.cuda.synchronize()  # expect: TG021
+        running += loss.detach()
+        all_labels.extend(y.numpy())
+        if step % 100 == 0:
+            print(running.item() / 100)
+        model.cpu()
+    print(running.item())
+    return total, preds, value, all_labels
+
+
+@torch.no_grad()
+def evaluate(model, loader):
+    model.eval()
+    correct = 0
+    for x, y in loader:
+        correct += (model(x).argmax(1) == y).sum().item()
+    return correct
@@ -0,0 +1,32 @@
+import torch
+
+
+def train(model, loader, optimizer, criterion):
+    for x, y in loader:
+        loss = criterion(model(x), y)
+        loss.backward()  # expect: TG003, TG006, TG008
+        optimizer.step()  # expect: TG009
+
+
+def bad_order(model, loader, optimizer, scheduler, criterion):
+    model.train()
+    for epoch in range(10):
+        scheduler.step()  # expect: TG013
+        for x, y in loader:
+            loss = criterion(model(x), y)
+            loss.backward()  # expect: TG006
+            optimizer.zero_grad()  # expect: TG012
+            torch.nn.utils.clip_grad_norm_(model.parameters(), 1.0)
+            optimizer.step()
+        model.eval()  # expect: TG014
+        for x, y in val_loader:
+            with torch.no_grad():
+                model(x)
+
+
+def no_backward(model, loader, optimizer, scheduler):
+    for x, y in loader:
+        optimizer.zero_grad()
+        model(x)
+        optimizer.step()  # expect: TG010
+        scheduler.step()
@@ -0,0 +1,95 @@
+//! Runs every rule over the annotated files in `tests/fixtures`.
+//!
+//! A line ending in `# expect: TG001` (or `# expect: TG001, TG011`) must get
+//! exactly those findings, and every other line none. When `name.fixed.py`
+//! exists next to `name.py`, it must equal `name.py` with every fix applied,
+//! unsafe ones included. Set `UPDATE_FIXTURES=1` to rewrite the `.fixed.py`
+//! files that already exist from the current output.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use rust_llm_qdrant::analyzer::CodeAnalyzer;
+use rust_llm_qdrant::cli::unified_diff;
+
+const FIXED_SUFFIX: &str = ".fixed.py";
+const EXPECT: &str = "# expect:";
+
+fn fixtures() -> Result<Vec<PathBuf>> {
+    // Tests run from the package root, which keeps reported paths short.
+    let dir = Path::new("tests/fixtures");
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| {
+        let name = path.to_string_lossy();
+        name.ends_with(".py") && !name.ends_with(FIXED_SUFFIX)
+    });
+    paths.sort();
+    Ok(paths)
+}
+
+/// The `(line, rule)` pairs announced by `# expect:` comments, sorted.
+fn expectations(code: &str) -> Vec<(usize, String)> {
+    let mut expected: Vec<(usize, String)> = code
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| Some((i + 1, line.split_once(EXPECT)?.1)))
+        .flat_map(|(line, ids)| ids.split(',').map(move |id| (line, id.trim().to_string())))
+        .filter(|(_, id)| !id.is_empty())
+        .collect();
+    expected.sort();
+    expected
+}
+
+#[test]
+fn test_rule_fixtures() -> Result<()> {
+    let update = std::env::var_os("UPDATE_FIXTURES").is_some();
+    let mut analyzer = CodeAnalyzer::new()?;
+    let mut failures = Vec::new();
+    let paths = fixtures()?;
+    assert!(!paths.is_empty(), "no fixtures found");
+
+    for path in &paths {
+        let code = fs::read_to_string(path)?;
+        let expected = expectations(&code);
+        let mut actual: Vec<(usize, String)> = analyzer
+            .analyze(&code)?
+            .into_iter()
+            .map(|finding| (finding.line as usize, finding.rule_id))
+            .collect();
+        actual.sort();
+
+        let missing: Vec<_> = expected.iter().filter(|e| !actual.contains(e)).collect();
+        let unexpected: Vec<_> = actual.iter().filter(|a| !expected.contains(a)).collect();
+        for (line, id) in missing {
+            failures.push(format!("{}:{}: expected {} but it was not reported", path.display(), line, id));
+        }
+        for (line, id) in unexpected {
+            failures.push(format!("{}:{}: unexpected {}", path.display(), line, id));
+        }
+
+        let golden = path.with_extension("fixed.py");
+        if golden.is_file() {
+            let fixed = analyzer.fix(&code, true)?;
+            if update {
+                fs::write(&golden, &fixed)?;
+            } else {
+                let want = fs::read_to_string(&golden)?;
+                if want != fixed {
+                    failures.push(format!("{} differs from the fixed output:\n{}", golden.display(), unified_diff(&golden, &want, &fixed)));
+                }
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{} fixture mismatch(es):\n{}", failures.len(), failures.join("\n"));
+    Ok(())
+}
+
+#[test]
+fn test_expectations_parse_lists() {
+    let code = "x = 1\nmodel.cuda()  # expect: TG001, TG011\n# expect:\n";
+    assert_eq!(expectations(code), [(2, "TG001".to_string()), (2, "TG011".to_string())]);
+}